@@ -0,0 +1,46 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use fizzy::fizz_buzz;
+use std::fmt::Write as _;
+use std::io::{self, Write as _};
+
+const COUNT: u64 = 100_000;
+
+fn bench_apply(c: &mut Criterion) {
+    let fizzer = fizz_buzz::<u64>();
+    let mut group = c.benchmark_group("fizz_buzz");
+    group.throughput(Throughput::Elements(COUNT));
+
+    group.bench_function("apply", |b| {
+        b.iter(|| {
+            let mut sink = io::sink();
+            for text in fizzer.apply(1..=black_box(COUNT)) {
+                sink.write_all(text.as_bytes()).unwrap();
+                sink.write_all(b"\n").unwrap();
+            }
+        })
+    });
+
+    group.bench_function("apply_to_writer", |b| {
+        b.iter(|| {
+            fizzer
+                .apply_to_writer(1..=black_box(COUNT), &mut io::sink(), "\n")
+                .unwrap()
+        })
+    });
+
+    let mut buffer = String::new();
+    group.bench_function("apply_to_fmt_writer", |b| {
+        b.iter(|| {
+            buffer.clear();
+            fizzer
+                .apply_to_fmt_writer(1..=black_box(COUNT), &mut buffer, "\n")
+                .unwrap();
+            buffer.write_char('\n').unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_apply);
+criterion_main!(benches);
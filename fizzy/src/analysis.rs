@@ -0,0 +1,153 @@
+use crate::{Condition, Fizzy};
+use std::fmt;
+
+/// the largest period which is tabulated or analysed
+const MAX_PERIOD: u64 = 1 << 16;
+
+/// Why a Fizzy cannot be analysed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnalysisError {
+    /// the matcher at this index is not built from divisibility rules
+    NotDivisibility(usize),
+    /// the outputs repeat only after more than `MAX_PERIOD` values
+    PeriodTooLarge,
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnalysisError::NotDivisibility(index) => {
+                write!(f, "matcher {} is not a divisibility rule", index)
+            }
+            AnalysisError::PeriodTooLarge => write!(f, "the period of the rule set is too large"),
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+/// Whenever `matcher` fires, `implies` fires as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Implication {
+    pub matcher: usize,
+    pub implies: usize,
+}
+
+/// One distinct output and how often it appears.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputDensity {
+    /// the joined substitutes, or None where the number itself is printed
+    pub output: Option<String>,
+    /// how many values in one period produce this output
+    pub count: u64,
+    /// the fraction of all values which produce this output
+    pub density: f64,
+}
+
+/// What a rule set made of divisibility rules does over one period.
+/// Matchers are referred to by the order in which they were added.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub period: u64,
+    /// matchers which never contribute to an output, either because their
+    /// condition never holds or because the strategy always silences them
+    pub unreachable: Vec<usize>,
+    /// pairs of matchers where one can only fire together with the other
+    pub redundant: Vec<Implication>,
+    /// every distinct output, in order of first appearance from zero
+    pub outputs: Vec<OutputDensity>,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    match b {
+        0 => a,
+        _ => gcd(b, a % b),
+    }
+}
+
+fn lcm(a: u64, b: u64) -> Option<u64> {
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+impl<T> Fizzy<T> {
+    /// the period of the outputs, if every matcher is a divisibility rule,
+    /// however large it is; `PeriodTooLarge` only when it overflows a u64
+    pub(crate) fn unbounded_period(&self) -> Result<u64, AnalysisError> {
+        let mut divisors = Vec::new();
+        for (index, matcher) in self.matchers.iter().enumerate() {
+            matcher
+                .condition
+                .divisors(&mut divisors)
+                .ok_or(AnalysisError::NotDivisibility(index))?;
+        }
+        divisors
+            .into_iter()
+            .try_fold(1, lcm)
+            .ok_or(AnalysisError::PeriodTooLarge)
+    }
+
+    /// the period of the outputs, if every matcher is a divisibility rule
+    /// and the period is small enough to tabulate
+    pub(crate) fn divisibility_period(&self) -> Result<u64, AnalysisError> {
+        self.unbounded_period()
+            .and_then(|period| match period <= MAX_PERIOD {
+                true => Ok(period),
+                false => Err(AnalysisError::PeriodTooLarge),
+            })
+    }
+
+    /// report unreachable and redundant matchers and the distribution of
+    /// outputs, for rule sets made only of divisibility rules
+    pub fn analyze(&self) -> Result<Analysis, AnalysisError> {
+        let period = self.divisibility_period()?;
+        let conditions = self
+            .matchers
+            .iter()
+            .map(|matcher| &matcher.condition)
+            .collect::<Vec<&Condition>>();
+
+        let mut hits = vec![0_u64; self.matchers.len()];
+        let mut outputs: Vec<OutputDensity> = Vec::new();
+        let mut fired = Vec::new();
+        for residue in 0..period {
+            self.collect_fired_by(&mut fired, |matcher| matcher.condition.holds(residue));
+            fired.iter().for_each(|&index| hits[index] += 1);
+            let output = self.join_fired(&fired);
+            match outputs.iter_mut().find(|density| density.output == output) {
+                Some(density) => density.count += 1,
+                None => outputs.push(OutputDensity {
+                    output,
+                    count: 1,
+                    density: 0.0,
+                }),
+            }
+        }
+        for density in outputs.iter_mut() {
+            density.density = density.count as f64 / period as f64;
+        }
+
+        let holds_anywhere = |index: usize| (0..period).any(|r| conditions[index].holds(r));
+        let implies = |a: usize, b: usize| {
+            (0..period).all(|r| !conditions[a].holds(r) || conditions[b].holds(r))
+        };
+        let redundant = (0..conditions.len())
+            .filter(|&a| holds_anywhere(a))
+            .flat_map(|a| {
+                (0..conditions.len())
+                    .filter(move |&b| a != b)
+                    .map(move |b| Implication {
+                        matcher: a,
+                        implies: b,
+                    })
+            })
+            .filter(|implication| implies(implication.matcher, implication.implies))
+            .collect();
+
+        Ok(Analysis {
+            period,
+            unreachable: (0..hits.len()).filter(|&index| hits[index] == 0).collect(),
+            redundant,
+            outputs,
+        })
+    }
+}
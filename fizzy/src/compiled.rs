@@ -0,0 +1,118 @@
+use crate::{values_between, Fizzy, History};
+use std::convert::{TryFrom, TryInto};
+use std::fmt::Display;
+use std::ops::{Add, Rem};
+
+/// A Fizzy prepared for repeated evaluation.
+///
+/// When every matcher is a divisibility rule, the outputs repeat with a period
+/// of lcm(divisors): one period of outputs is precomputed and every value is
+/// answered by table lookup. Any other rule set runs its matchers as usual.
+pub struct Compiled<T> {
+    fizzy: Fizzy<T>,
+    table: Option<Table<T>>,
+}
+
+struct Table<T> {
+    period: u64,
+    /// the period as a T, or None if T cannot represent it
+    period_t: Option<T>,
+    /// the joined substitutes for every residue, None where the number is printed
+    outputs: Vec<Option<String>>,
+}
+
+impl<T> Fizzy<T>
+where
+    T: TryFrom<u64>,
+{
+    /// precompute this fizzy into a lookup table if its matchers allow it
+    pub fn compile(&self) -> Compiled<T> {
+        let table = self.divisibility_period().ok().map(|period| {
+            let mut fired = Vec::new();
+            let outputs = (0..period)
+                .map(|residue| {
+                    self.collect_fired_by(&mut fired, |matcher| matcher.condition.holds(residue));
+                    self.join_fired(&fired)
+                })
+                .collect();
+            Table {
+                period,
+                period_t: T::try_from(period).ok(),
+                outputs,
+            }
+        });
+        Compiled {
+            fizzy: self.clone(),
+            table,
+        }
+    }
+}
+
+impl<T> Compiled<T>
+where
+    T: Display + Clone + Add<Output = T> + Rem<Output = T> + TryInto<u64> + TryFrom<u64>,
+{
+    /// the length of the lookup table, or None if the matchers are run directly
+    pub fn period(&self) -> Option<u64> {
+        self.table.as_ref().map(|table| table.period)
+    }
+
+    /// the position of `element` in the lookup table, if it has one
+    fn residue(table: &Table<T>, element: &T) -> Option<usize> {
+        let remainder = match &table.period_t {
+            Some(period) => element.clone() % period.clone(),
+            None => element.clone(),
+        };
+        remainder
+            .clone()
+            .try_into()
+            .ok()
+            // a negative remainder is shifted back into the table
+            .or_else(|| {
+                table
+                    .period_t
+                    .clone()
+                    .and_then(|period| (remainder + period).try_into().ok())
+            })
+            .map(|residue| residue as usize)
+    }
+
+    /// the output for a single value, found in constant time when tabulated
+    pub fn evaluate(&self, element: T) -> String {
+        let found = self.table.as_ref().and_then(|table| {
+            Compiled::residue(table, &element).map(|residue| &table.outputs[residue])
+        });
+        match found {
+            Some(Some(words)) => self.fizzy.format_words(&element, words),
+            Some(None) => self.fizzy.format_value(&element),
+            None => self.fizzy.evaluate(element),
+        }
+    }
+
+    /// map the compiled fizzy onto every element of an iterator, like `Fizzy::apply`
+    pub fn apply<'a, I>(&'a self, iter: I) -> impl Iterator<Item = String> + 'a
+    where
+        I: Iterator<Item = T> + 'a,
+    {
+        let (mut fired, mut history) = (Vec::new(), History::default());
+        iter.map(move |n| match self.table {
+            Some(_) => self.evaluate(n),
+            // stateful matchers are never tabulated and need the history
+            None => self.fizzy.evaluate_next(&n, &mut history, &mut fired),
+        })
+    }
+
+    /// the outputs for every value in `[start, end)`, like `Fizzy::range`
+    pub fn range(&self, start: T, end: T) -> impl Iterator<Item = String> + '_
+    where
+        T: From<u8> + PartialOrd,
+    {
+        self.apply(values_between(start, end))
+    }
+
+    /// the output for the number `n`, found in constant time when tabulated,
+    /// or None if `n` cannot be represented in T
+    pub fn nth(&self, n: u64) -> Option<String> {
+        T::try_from(n).ok().map(|element| self.evaluate(element))
+    }
+}
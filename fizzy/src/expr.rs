@@ -0,0 +1,637 @@
+//! A small expression language for writing matchers as text, such as
+//! `n % 7 == 0 && n > 100 => "bang"`.
+//!
+//! Expressions are made of the element `n`, integer literals, `true` and
+//! `false`, the arithmetic operators `+ - * / %`, the comparisons
+//! `== != < <= > >=`, the boolean operators `&& || !` and parentheses, with
+//! the usual precedence. Arithmetic is done on `i128`: an element which does
+//! not fit, an overflow or a division by zero makes the expression false.
+
+use crate::{Matcher, Rule};
+use std::convert::TryInto;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Not,
+    Negate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+/// A parsed, type-checked expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(i128),
+    Bool(bool),
+    /// the element being matched, `n`
+    Element,
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnexpectedCharacter(char),
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+    },
+    UnknownName(String),
+    NumberTooLarge,
+    UnterminatedString,
+    /// an operand of the wrong type, e.g. `!3` or `n && true`
+    TypeMismatch {
+        expected: &'static str,
+    },
+    /// more than `MAX_DEPTH` levels of nesting
+    TooDeep,
+}
+
+/// An error in an expression, with the 1-based column where it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: ", self.column)?;
+        match &self.kind {
+            ParseErrorKind::UnexpectedCharacter(c) => write!(f, "unexpected character '{}'", c),
+            ParseErrorKind::UnexpectedToken { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            ParseErrorKind::UnknownName(name) => {
+                write!(f, "unknown name '{}', the element is called 'n'", name)
+            }
+            ParseErrorKind::NumberTooLarge => write!(f, "number too large"),
+            ParseErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            ParseErrorKind::TypeMismatch { expected } => write!(f, "expected {}", expected),
+            ParseErrorKind::TooDeep => write!(f, "expression nested too deeply"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i128),
+    Name(String),
+    Str(String),
+    Symbol(&'static str),
+    Arrow,
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "'{}'", n),
+            Token::Name(name) => write!(f, "'{}'", name),
+            Token::Str(_) => write!(f, "a string"),
+            Token::Symbol(symbol) => write!(f, "'{}'", symbol),
+            Token::Arrow => write!(f, "'=>'"),
+            Token::End => write!(f, "end of input"),
+        }
+    }
+}
+
+/// longest symbols first, so that `<=` is not read as `<`
+const SYMBOLS: [&str; 16] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "%", "(", ")",
+];
+
+/// how deeply an expression may nest, counting parentheses, unary operators
+/// and the operands of a chain such as `n + 1 + 2`, so that parsing and
+/// evaluating a hostile expression cannot overflow the stack
+const MAX_DEPTH: usize = 100;
+
+fn error<T>(column: usize, kind: ParseErrorKind) -> Result<T, ParseError> {
+    Err(ParseError { column, kind })
+}
+
+/// split the source into tokens, each with its 1-based column
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let column = i + 1;
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let digits = chars[start..i].iter().collect::<String>();
+            match digits.parse() {
+                Ok(n) => tokens.push((column, Token::Number(n))),
+                Err(_) => return error(column, ParseErrorKind::NumberTooLarge),
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((column, Token::Name(chars[start..i].iter().collect())));
+        } else if c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return error(column, ParseErrorKind::UnterminatedString),
+                    Some('"') => break,
+                    Some('\\') if i + 1 < chars.len() => {
+                        text.push(chars[i + 1]);
+                        i += 2;
+                    }
+                    Some(&c) => {
+                        text.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+            tokens.push((column, Token::Str(text)));
+        } else if chars[i..].starts_with(&['=', '>']) {
+            tokens.push((column, Token::Arrow));
+            i += 2;
+        } else {
+            let rest = chars[i..].iter().take(2).collect::<String>();
+            match SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+                Some(&symbol) => {
+                    tokens.push((column, Token::Symbol(symbol)));
+                    i += symbol.len();
+                }
+                None => return error(column, ParseErrorKind::UnexpectedCharacter(c)),
+            }
+        }
+    }
+    tokens.push((chars.len() + 1, Token::End));
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Int,
+    Bool,
+}
+
+impl Type {
+    fn describe(self) -> &'static str {
+        match self {
+            Type::Int => "a number",
+            Type::Bool => "a condition",
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    position: usize,
+    /// the current nesting, up to `MAX_DEPTH`
+    depth: usize,
+}
+
+type Typed = (Expr, Type);
+
+impl Parser {
+    fn new(source: &str) -> Result<Parser, ParseError> {
+        Ok(Parser {
+            tokens: tokenize(source)?,
+            position: 0,
+            depth: 0,
+        })
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position].1
+    }
+
+    fn column(&self) -> usize {
+        self.tokens[self.position].0
+    }
+
+    fn unexpected<T>(&self, expected: &'static str) -> Result<T, ParseError> {
+        error(
+            self.column(),
+            ParseErrorKind::UnexpectedToken {
+                expected,
+                found: self.peek().to_string(),
+            },
+        )
+    }
+
+    /// go one level deeper for the construct at `column`
+    fn nest(&mut self, column: usize) -> Result<(), ParseError> {
+        self.depth += 1;
+        match self.depth > MAX_DEPTH {
+            true => error(column, ParseErrorKind::TooDeep),
+            false => Ok(()),
+        }
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        match self.peek() {
+            Token::Symbol(s) if *s == symbol => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// the operator at the current token, if it is one of `operators`
+    fn operator(&self, operators: &[(&str, BinaryOp)]) -> Option<BinaryOp> {
+        match self.peek() {
+            Token::Symbol(s) => operators
+                .iter()
+                .find(|(symbol, _)| symbol == s)
+                .map(|&(_, op)| op),
+            _ => None,
+        }
+    }
+
+    fn expect_type(
+        column: usize,
+        (expr, found): Typed,
+        expected: Type,
+    ) -> Result<Expr, ParseError> {
+        match found == expected {
+            true => Ok(expr),
+            false => error(
+                column,
+                ParseErrorKind::TypeMismatch {
+                    expected: expected.describe(),
+                },
+            ),
+        }
+    }
+
+    /// parse a left-associative chain of `operators` whose operands are
+    /// produced by `operand` and must have the type `operand_type`
+    fn chain(
+        &mut self,
+        operators: &[(&str, BinaryOp)],
+        operand_type: Type,
+        result_type: Type,
+        operand: fn(&mut Parser) -> Result<Typed, ParseError>,
+    ) -> Result<Typed, ParseError> {
+        let (column, depth) = (self.column(), self.depth);
+        let mut left = operand(self)?;
+        while let Some(op) = self.operator(operators) {
+            let left_expr = Parser::expect_type(column, left, operand_type)?;
+            // every operator puts the operands before it one level deeper
+            self.nest(self.column())?;
+            self.position += 1;
+            let column = self.column();
+            let right = Parser::expect_type(column, operand(self)?, operand_type)?;
+            left = (
+                Expr::Binary(op, Box::new(left_expr), Box::new(right)),
+                result_type,
+            );
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Typed, ParseError> {
+        self.chain(&[("||", BinaryOp::Or)], Type::Bool, Type::Bool, Parser::and)
+    }
+
+    fn and(&mut self) -> Result<Typed, ParseError> {
+        self.chain(
+            &[("&&", BinaryOp::And)],
+            Type::Bool,
+            Type::Bool,
+            Parser::comparison,
+        )
+    }
+
+    fn comparison(&mut self) -> Result<Typed, ParseError> {
+        const OPERATORS: [(&str, BinaryOp); 6] = [
+            ("==", BinaryOp::Eq),
+            ("!=", BinaryOp::Ne),
+            ("<", BinaryOp::Lt),
+            ("<=", BinaryOp::Le),
+            (">", BinaryOp::Gt),
+            (">=", BinaryOp::Ge),
+        ];
+        let left_column = self.column();
+        let left = self.sum()?;
+        let op = match self.operator(&OPERATORS) {
+            Some(op) => op,
+            None => return Ok(left),
+        };
+        self.position += 1;
+        // equality works on conditions as well, ordering only on numbers
+        let operand_type = match op {
+            BinaryOp::Eq | BinaryOp::Ne => left.1,
+            _ => Type::Int,
+        };
+        let left = Parser::expect_type(left_column, left, operand_type)?;
+        let right_column = self.column();
+        let right = Parser::expect_type(right_column, self.sum()?, operand_type)?;
+        if self.operator(&OPERATORS).is_some() {
+            return self.unexpected("'&&', '||' or ')' between comparisons");
+        }
+        Ok((
+            Expr::Binary(op, Box::new(left), Box::new(right)),
+            Type::Bool,
+        ))
+    }
+
+    fn sum(&mut self) -> Result<Typed, ParseError> {
+        self.chain(
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+            Type::Int,
+            Type::Int,
+            Parser::product,
+        )
+    }
+
+    fn product(&mut self) -> Result<Typed, ParseError> {
+        self.chain(
+            &[
+                ("*", BinaryOp::Mul),
+                ("/", BinaryOp::Div),
+                ("%", BinaryOp::Rem),
+            ],
+            Type::Int,
+            Type::Int,
+            Parser::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Result<Typed, ParseError> {
+        let operator_column = self.column();
+        let (op, operand_type) = if self.eat("!") {
+            (UnaryOp::Not, Type::Bool)
+        } else if self.eat("-") {
+            (UnaryOp::Negate, Type::Int)
+        } else {
+            return self.primary();
+        };
+        self.nest(operator_column)?;
+        let column = self.column();
+        let operand = Parser::expect_type(column, self.unary()?, operand_type)?;
+        self.depth -= 1;
+        Ok((Expr::Unary(op, Box::new(operand)), operand_type))
+    }
+
+    fn primary(&mut self) -> Result<Typed, ParseError> {
+        let column = self.column();
+        match self.peek().clone() {
+            Token::Number(n) => {
+                self.position += 1;
+                Ok((Expr::Number(n), Type::Int))
+            }
+            Token::Name(name) => {
+                self.position += 1;
+                match name.as_str() {
+                    "n" => Ok((Expr::Element, Type::Int)),
+                    "true" => Ok((Expr::Bool(true), Type::Bool)),
+                    "false" => Ok((Expr::Bool(false), Type::Bool)),
+                    _ => error(column, ParseErrorKind::UnknownName(name)),
+                }
+            }
+            Token::Symbol("(") => {
+                self.nest(column)?;
+                self.position += 1;
+                let inner = self.or()?;
+                self.depth -= 1;
+                match self.eat(")") {
+                    true => Ok(inner),
+                    false => self.unexpected("')'"),
+                }
+            }
+            _ => self.unexpected("an expression"),
+        }
+    }
+
+    fn condition(&mut self) -> Result<Expr, ParseError> {
+        let column = self.column();
+        let condition = self.or()?;
+        Parser::expect_type(column, condition, Type::Bool)
+    }
+}
+
+enum Value {
+    Int(i128),
+    Bool(bool),
+}
+
+impl Expr {
+    /// parse a condition such as `n % 7 == 0 && n > 100`
+    pub fn parse(source: &str) -> Result<Expr, ParseError> {
+        let mut parser = Parser::new(source)?;
+        let condition = parser.condition()?;
+        match parser.peek() {
+            Token::End => Ok(condition),
+            _ => parser.unexpected("an operator or end of input"),
+        }
+    }
+
+    fn eval(&self, n: i128) -> Option<Value> {
+        let int = |expr: &Expr| match expr.eval(n)? {
+            Value::Int(i) => Some(i),
+            Value::Bool(_) => None,
+        };
+        let boolean = |expr: &Expr| match expr.eval(n)? {
+            Value::Bool(b) => Some(b),
+            Value::Int(_) => None,
+        };
+        let value = match self {
+            Expr::Number(i) => Value::Int(*i),
+            Expr::Bool(b) => Value::Bool(*b),
+            Expr::Element => Value::Int(n),
+            Expr::Unary(UnaryOp::Not, inner) => Value::Bool(!boolean(inner)?),
+            Expr::Unary(UnaryOp::Negate, inner) => Value::Int(int(inner)?.checked_neg()?),
+            Expr::Binary(BinaryOp::Or, left, right) => {
+                Value::Bool(boolean(left)? || boolean(right)?)
+            }
+            Expr::Binary(BinaryOp::And, left, right) => {
+                Value::Bool(boolean(left)? && boolean(right)?)
+            }
+            Expr::Binary(op @ (BinaryOp::Eq | BinaryOp::Ne), left, right) => {
+                let equal = match (left.eval(n)?, right.eval(n)?) {
+                    (Value::Int(a), Value::Int(b)) => a == b,
+                    (Value::Bool(a), Value::Bool(b)) => a == b,
+                    _ => return None,
+                };
+                Value::Bool(equal == (*op == BinaryOp::Eq))
+            }
+            Expr::Binary(op, left, right) => {
+                let (a, b) = (int(left)?, int(right)?);
+                match op {
+                    BinaryOp::Lt => Value::Bool(a < b),
+                    BinaryOp::Le => Value::Bool(a <= b),
+                    BinaryOp::Gt => Value::Bool(a > b),
+                    BinaryOp::Ge => Value::Bool(a >= b),
+                    BinaryOp::Add => Value::Int(a.checked_add(b)?),
+                    BinaryOp::Sub => Value::Int(a.checked_sub(b)?),
+                    BinaryOp::Mul => Value::Int(a.checked_mul(b)?),
+                    BinaryOp::Div => Value::Int(a.checked_div(b)?),
+                    BinaryOp::Rem => Value::Int(a.checked_rem(b)?),
+                    _ => unreachable!("handled above"),
+                }
+            }
+        };
+        Some(value)
+    }
+
+    /// whether the condition holds for the element `n`
+    pub fn matches(&self, n: i128) -> bool {
+        matches!(self.eval(n), Some(Value::Bool(true)))
+    }
+
+    /// a matcher which substitutes `substitute` wherever this condition holds
+    pub fn into_matcher<T, S>(self, substitute: S) -> Matcher<T>
+    where
+        T: Clone + TryInto<i128>,
+        S: Into<String>,
+    {
+        let rule = Rule::Expression(self.to_string());
+        Matcher {
+            rule: Some(rule),
+            ..Matcher::new_ref(
+                move |n: &T| n.clone().try_into().is_ok_and(|n| self.matches(n)),
+                substitute,
+            )
+        }
+    }
+}
+
+impl BinaryOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Or => "||",
+            BinaryOp::And => "&&",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+        }
+    }
+
+    /// how tightly the operator binds, as in the parser
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge => 3,
+            BinaryOp::Add | BinaryOp::Sub => 4,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 5,
+        }
+    }
+}
+
+impl Expr {
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Binary(op, _, _) => op.precedence(),
+            Expr::Unary(..) => 6,
+            _ => 7,
+        }
+    }
+}
+
+/// `expr`, in parentheses unless it binds at least as tightly as `precedence`
+fn write_operand(f: &mut fmt::Formatter, expr: &Expr, precedence: u8) -> fmt::Result {
+    match expr.precedence() < precedence {
+        true => write!(f, "({})", expr),
+        false => write!(f, "{}", expr),
+    }
+}
+
+/// Prints the expression back as source, with only the parentheses it needs.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Bool(b) => write!(f, "{}", b),
+            Expr::Element => write!(f, "n"),
+            Expr::Unary(op, inner) => {
+                f.write_str(match op {
+                    UnaryOp::Not => "!",
+                    UnaryOp::Negate => "-",
+                })?;
+                write_operand(f, inner, 6)
+            }
+            Expr::Binary(op, left, right) => {
+                // operators are left-associative, and comparisons do not chain
+                let precedence = op.precedence();
+                let left_precedence = match precedence {
+                    3 => precedence + 1,
+                    _ => precedence,
+                };
+                write_operand(f, left, left_precedence)?;
+                write!(f, " {} ", op.symbol())?;
+                write_operand(f, right, precedence + 1)
+            }
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = ParseError;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        Expr::parse(source)
+    }
+}
+
+/// parse a whole rule of the form `CONDITION => "WORD"` into a matcher
+pub fn parse_rule<T>(source: &str) -> Result<Matcher<T>, ParseError>
+where
+    T: Clone + TryInto<i128>,
+{
+    let mut parser = Parser::new(source)?;
+    let condition = parser.condition()?;
+    if parser.peek() != &Token::Arrow {
+        return parser.unexpected("'=>'");
+    }
+    parser.position += 1;
+    let word = match parser.peek().clone() {
+        Token::Str(word) => word,
+        _ => return parser.unexpected("a quoted word"),
+    };
+    parser.position += 1;
+    match parser.peek() {
+        Token::End => Ok(condition.into_matcher(word)),
+        _ => parser.unexpected("end of input"),
+    }
+}
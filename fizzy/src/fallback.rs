@@ -0,0 +1,102 @@
+//! Formatters for the elements which no matcher fired for, to be passed to
+//! `Fizzy::with_fallback`. Elements which a formatter cannot represent are
+//! printed with `Display`.
+
+use std::convert::TryInto;
+use std::fmt::Display;
+
+/// lowercase hexadecimal, e.g. `ff` for 255 and `-1a` for -26
+pub fn hexadecimal<T>() -> impl Fn(&T) -> String + Send + Sync + Clone
+where
+    T: Display + Clone + TryInto<i128>,
+{
+    |n: &T| match n.clone().try_into() {
+        Ok(value) if value < 0 => format!("-{:x}", value.unsigned_abs()),
+        Ok(value) => format!("{:x}", value),
+        Err(_) => n.to_string(),
+    }
+}
+
+const ROMAN_NUMERALS: [(u16, &str); 13] = [
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+/// roman numerals for 1 to 3999, e.g. `XIV` for 14
+pub fn roman<T>() -> impl Fn(&T) -> String + Send + Sync + Clone
+where
+    T: Display + Clone + TryInto<u16>,
+{
+    |n: &T| match n.clone().try_into() {
+        Ok(mut value) if (1..4000).contains(&value) => {
+            let mut numeral = String::new();
+            for &(amount, symbol) in ROMAN_NUMERALS.iter() {
+                while value >= amount {
+                    numeral.push_str(symbol);
+                    value -= amount;
+                }
+            }
+            numeral
+        }
+        _ => n.to_string(),
+    }
+}
+
+/// split the printed element into its sign and the rest
+fn split_sign(text: &str) -> (&str, &str) {
+    match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text),
+    }
+}
+
+/// padded with zeros to at least `width` characters, counting the sign,
+/// like the `{:0width$}` format
+pub fn zero_padded<T>(width: usize) -> impl Fn(&T) -> String + Send + Sync + Clone
+where
+    T: Display,
+{
+    move |n: &T| {
+        let text = n.to_string();
+        let (sign, digits) = split_sign(&text);
+        let padding = width.saturating_sub(sign.len() + digits.chars().count());
+        format!("{}{}{}", sign, "0".repeat(padding), digits)
+    }
+}
+
+/// the integer digits grouped in threes with `separator`, e.g. `1,234,567`
+/// with `','` or `1 234 567` with `' '`
+pub fn thousands<T>(separator: char) -> impl Fn(&T) -> String + Send + Sync + Clone
+where
+    T: Display,
+{
+    move |n: &T| {
+        let text = n.to_string();
+        let (sign, unsigned) = split_sign(&text);
+        let split = unsigned
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(unsigned.len());
+        let (integer, rest) = unsigned.split_at(split);
+
+        let mut grouped = String::from(sign);
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped.push_str(rest);
+        grouped
+    }
+}
@@ -0,0 +1,200 @@
+use crate::Fizzy;
+use std::fmt::{self, Display, Write};
+
+/// How the letters of the matched words are cased.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Casing {
+    /// keep the substitutes as they are
+    #[default]
+    AsIs,
+    Lower,
+    Upper,
+    /// capitalise every substitute, e.g. `Fizz-Buzz`
+    Title,
+    /// capitalise only the first letter of the output, e.g. `Fizz-buzz`
+    CapitaliseFirst,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Words,
+    Value,
+}
+
+/// An output template such as `{words} ({n})`, split into its parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Template {
+    segments: Vec<Segment>,
+}
+
+impl Template {
+    fn parse(template: &str) -> Template {
+        let mut segments = Vec::new();
+        let mut rest = template;
+        while !rest.is_empty() {
+            let (segment, length) = if rest.starts_with("{words}") {
+                (Segment::Words, "{words}".len())
+            } else if rest.starts_with("{n}") {
+                (Segment::Value, "{n}".len())
+            } else {
+                // up to the next brace which could start a placeholder
+                let length = rest
+                    .char_indices()
+                    .skip(1)
+                    .find(|&(_, c)| c == '{')
+                    .map_or(rest.len(), |(i, _)| i);
+                (Segment::Literal(String::from(&rest[..length])), length)
+            };
+            segments.push(segment);
+            rest = &rest[length..];
+        }
+        Template { segments }
+    }
+}
+
+fn write_cased<W: Write>(writer: &mut W, c: char, upper: bool) -> fmt::Result {
+    match upper {
+        true => c.to_uppercase().try_for_each(|c| writer.write_char(c)),
+        false => c.to_lowercase().try_for_each(|c| writer.write_char(c)),
+    }
+}
+
+impl<T> Fizzy<T> {
+    /// put `joiner` between the words when more than one matcher fires;
+    /// the default is to join them directly, as in `fizzbuzz`
+    pub fn with_joiner<S: Into<String>>(self, joiner: S) -> Self {
+        Fizzy {
+            joiner: joiner.into(),
+            ..self
+        }
+    }
+
+    /// change the letter case of the matched words
+    pub fn with_casing(self, casing: Casing) -> Self {
+        Fizzy { casing, ..self }
+    }
+
+    /// render matched outputs through `template`, after joining and casing:
+    /// `{words}` is replaced by the words and `{n}` by the formatted element.
+    /// Elements which no matcher fired for are not templated.
+    pub fn with_template(self, template: &str) -> Self {
+        Fizzy {
+            template: Some(Template::parse(template)),
+            ..self
+        }
+    }
+
+    /// write the substitutes of the fired matchers, joined and cased
+    pub(crate) fn write_words<W: Write>(&self, fired: &[usize], writer: &mut W) -> fmt::Result {
+        let mut at_start = true;
+        for (position, &index) in fired.iter().enumerate() {
+            if position > 0 {
+                writer.write_str(&self.joiner)?;
+            }
+            let word = self.word(index);
+            if self.casing == Casing::AsIs {
+                writer.write_str(word)?;
+                continue;
+            }
+            for (i, c) in word.chars().enumerate() {
+                let upper = match self.casing {
+                    Casing::Upper => true,
+                    Casing::Title => i == 0,
+                    Casing::CapitaliseFirst => at_start,
+                    Casing::Lower | Casing::AsIs => false,
+                };
+                write_cased(writer, c, upper)?;
+                at_start = false;
+            }
+        }
+        Ok(())
+    }
+
+    /// the joined and cased words of the fired matchers, None if none fired
+    pub(crate) fn join_fired(&self, fired: &[usize]) -> Option<String> {
+        match fired {
+            [] => None,
+            [index] if self.casing == Casing::AsIs => Some(String::from(self.word(*index))),
+            _ => {
+                let mut words = String::new();
+                self.write_words(fired, &mut words).ok()?;
+                Some(words)
+            }
+        }
+    }
+}
+
+impl<T> Fizzy<T>
+where
+    T: Display,
+{
+    pub(crate) fn write_value<W: Write>(&self, value: &T, writer: &mut W) -> fmt::Result {
+        match &self.fallback {
+            Some(fallback) => writer.write_str(&fallback(value)),
+            None => write!(writer, "{}", value),
+        }
+    }
+
+    pub(crate) fn format_value(&self, value: &T) -> String {
+        match &self.fallback {
+            Some(fallback) => fallback(value),
+            None => value.to_string(),
+        }
+    }
+
+    /// write matched words for `value` through the template, if there is one
+    fn write_templated<W, F>(&self, value: &T, writer: &mut W, write_words: F) -> fmt::Result
+    where
+        W: Write,
+        F: Fn(&mut W) -> fmt::Result,
+    {
+        let segments = match &self.template {
+            Some(template) => &template.segments,
+            None => return write_words(writer),
+        };
+        segments.iter().try_for_each(|segment| match segment {
+            Segment::Literal(text) => writer.write_str(text),
+            Segment::Words => write_words(writer),
+            Segment::Value => self.write_value(value, writer),
+        })
+    }
+
+    /// write the complete output for `value`, given the matchers which fired
+    pub(crate) fn write_output<W: Write>(
+        &self,
+        value: &T,
+        fired: &[usize],
+        writer: &mut W,
+    ) -> fmt::Result {
+        match fired {
+            [] => self.write_value(value, writer),
+            _ => self.write_templated(value, writer, |writer| self.write_words(fired, writer)),
+        }
+    }
+
+    pub(crate) fn format_output(&self, value: &T, fired: &[usize]) -> String {
+        match (fired, &self.template) {
+            ([], _) => self.format_value(value),
+            (_, None) => self.join_fired(fired).unwrap_or_default(),
+            (_, Some(_)) => {
+                let mut output = String::new();
+                // writing into a String cannot fail
+                let _ = self.write_output(value, fired, &mut output);
+                output
+            }
+        }
+    }
+
+    /// the complete output for `value` from words which were joined already
+    pub(crate) fn format_words(&self, value: &T, words: &str) -> String {
+        match &self.template {
+            None => String::from(words),
+            Some(_) => {
+                let mut output = String::new();
+                let _ = self.write_templated(value, &mut output, |writer| writer.write_str(words));
+                output
+            }
+        }
+    }
+}
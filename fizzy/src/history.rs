@@ -0,0 +1,62 @@
+use crate::Fizzy;
+
+/// What a stateful matcher knows about the sequence it is applied to: the
+/// position of the current element and the outputs before it.
+///
+/// Only the previous output is kept in full, so that applying a stateful
+/// Fizzy to a long or endless iterator takes constant memory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct History {
+    index: usize,
+    words: usize,
+    /// the words of the previous output, empty if it was a number
+    previous: Vec<String>,
+}
+
+impl History {
+    /// the position of the current element, counting from 0
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// how many of the earlier outputs were words rather than numbers
+    pub fn words_so_far(&self) -> usize {
+        self.words
+    }
+
+    /// how many of the earlier outputs were numbers
+    pub fn numbers_so_far(&self) -> usize {
+        self.index - self.words
+    }
+
+    /// the words which made up the previous output, before joining and
+    /// casing; empty if it was a number, None for the first element
+    pub fn previous(&self) -> Option<&[String]> {
+        match self.index {
+            0 => None,
+            _ => Some(&self.previous),
+        }
+    }
+
+    /// whether there was a previous output and it was a number
+    pub fn previous_was_number(&self) -> bool {
+        self.previous().is_some_and(<[String]>::is_empty)
+    }
+}
+
+impl<T> Fizzy<T> {
+    /// move `history` on past an element for which `fired` fired
+    pub(crate) fn record(&self, history: &mut History, fired: &[usize]) {
+        history.index += 1;
+        if !fired.is_empty() {
+            history.words += 1;
+        }
+        // nothing else can read the substitutes
+        if self.stateful {
+            history.previous.clear();
+            history
+                .previous
+                .extend(fired.iter().map(|&index| String::from(self.word(index))));
+        }
+    }
+}
@@ -0,0 +1,334 @@
+//! Working backwards from a sample of output, such as `1, 2, fizz, 4, buzz`,
+//! to the divisibility rules which produce it.
+
+use crate::{Fizzy, Matcher};
+use std::fmt;
+
+/// A divisibility rule found by `infer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InferredRule {
+    pub divisor: u64,
+    pub word: String,
+}
+
+/// The smallest rule set which reproduces a sample, both as rules in
+/// evaluation order and as a ready Fizzy.
+#[derive(Clone)]
+pub struct Inference {
+    pub fizzy: Fizzy<u64>,
+    pub rules: Vec<InferredRule>,
+}
+
+impl fmt::Debug for Inference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inference")
+            .field("rules", &self.rules)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Why no rule set could be inferred from a sample.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InferenceError {
+    /// the output for `value` is a number other than `value`, which suggests
+    /// the sample does not start where it was said to
+    WrongNumber { value: u64, found: String },
+    /// the output for `value` is empty, which no word can produce
+    EmptyOutput { value: u64 },
+    /// no set of divisibility rules produces `output` for `value` together
+    /// with the outputs before it
+    NoRuleSet { value: u64, output: String },
+}
+
+impl fmt::Display for InferenceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InferenceError::WrongNumber { value, found } => {
+                write!(
+                    f,
+                    "expected {} or words for {}, found {}",
+                    value, value, found
+                )
+            }
+            InferenceError::EmptyOutput { value } => write!(f, "the output for {} is empty", value),
+            InferenceError::NoRuleSet { value, output } => write!(
+                f,
+                "no divisibility rules explain \"{}\" for {} together with the outputs before it",
+                output, value
+            ),
+        }
+    }
+}
+
+impl std::error::Error for InferenceError {}
+
+/// A depth-first search for rules, which visits the word outputs in order and
+/// adds a rule for a divisor when its first multiple is reached.
+struct Search<'a> {
+    /// the values whose outputs are words, in increasing order, except zero
+    words: Vec<(u64, &'a str)>,
+    /// the values which are printed as themselves, except zero
+    numbers: Vec<u64>,
+    /// the output for zero, if it is in the sample
+    zero: Option<&'a str>,
+    /// the smallest and largest positive values in the sample
+    first: u64,
+    last: u64,
+    /// the rules found so far, in evaluation order
+    rules: Vec<InferredRule>,
+    /// how many rules the current attempt may use
+    budget: usize,
+    /// whether the current attempt ran into its budget
+    limited: bool,
+    /// how many word outputs the best attempt so far explained
+    furthest: usize,
+}
+
+/// A stretch of an output which new rules have to produce, and where in the
+/// evaluation order they can go.
+struct Gap<'a> {
+    text: &'a str,
+    first_slot: usize,
+    last_slot: usize,
+}
+
+impl<'a> Search<'a> {
+    /// the value and output at `position`, with zero coming last
+    fn entry(&self, position: usize) -> Option<(u64, &'a str)> {
+        match self.words.get(position) {
+            Some(&word) => Some(word),
+            None if position == self.words.len() => self.zero.map(|output| (0, output)),
+            None => None,
+        }
+    }
+
+    fn search(&mut self, position: usize) -> bool {
+        self.furthest = self.furthest.max(position);
+        let (value, output) = match self.entry(position) {
+            Some((0, "0")) => return self.rules.is_empty(),
+            Some(entry) => entry,
+            None => return true,
+        };
+        let firing = (0..self.rules.len())
+            .filter(|&index| value.is_multiple_of(self.rules[index].divisor))
+            .collect::<Vec<_>>();
+        self.match_words(position, output, &firing, 0, &mut Vec::new())
+    }
+
+    /// find the words of the `firing` rules in `output`, in order, and
+    /// collect the stretches between them into `gaps`
+    fn match_words(
+        &mut self,
+        position: usize,
+        output: &'a str,
+        firing: &[usize],
+        offset: usize,
+        gaps: &mut Vec<Gap<'a>>,
+    ) -> bool {
+        let first_slot = match gaps.len() {
+            0 => 0,
+            matched => firing[matched - 1] + 1,
+        };
+        let next = match firing.get(gaps.len()) {
+            Some(&next) => next,
+            None => {
+                gaps.push(Gap {
+                    text: &output[offset..],
+                    first_slot,
+                    last_slot: self.rules.len(),
+                });
+                let found = self.fill_gaps(position, gaps);
+                gaps.pop();
+                return found;
+            }
+        };
+        let word = self.rules[next].word.clone();
+        let starts = output[offset..]
+            .match_indices(word.as_str())
+            .map(|(start, _)| offset + start)
+            .collect::<Vec<_>>();
+        for start in starts {
+            gaps.push(Gap {
+                text: &output[offset..start],
+                first_slot,
+                last_slot: next,
+            });
+            let found = self.match_words(position, output, firing, start + word.len(), gaps);
+            gaps.pop();
+            if found {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// whether `more` new rules still fit in the budget
+    fn affords(&mut self, more: usize) -> bool {
+        let affords = self.rules.len() + more <= self.budget;
+        self.limited |= !affords;
+        affords
+    }
+
+    /// add new rules for the non-empty gaps, then move on to the next output
+    fn fill_gaps(&mut self, position: usize, gaps: &[Gap<'a>]) -> bool {
+        let needed = gaps.iter().filter(|gap| !gap.text.is_empty()).count();
+        if !self.affords(needed) {
+            return false;
+        }
+        // the last gap is filled first, so the slots of the others stay put
+        match gaps.split_last() {
+            None => self.search(position + 1),
+            Some((gap, _)) if gap.text.is_empty() => {
+                self.fill_gaps(position, &gaps[..gaps.len() - 1])
+            }
+            Some((gap, rest)) => {
+                self.fill_gap(position, gap.text, gap.first_slot, gap.last_slot, rest)
+            }
+        }
+    }
+
+    /// add rules whose words make up `text`, at slots from `first_slot` to
+    /// `last_slot`, the last word first
+    fn fill_gap(
+        &mut self,
+        position: usize,
+        text: &'a str,
+        first_slot: usize,
+        last_slot: usize,
+        rest: &[Gap<'a>],
+    ) -> bool {
+        let value = self.entry(position).map_or(0, |(value, _)| value);
+        let needed = rest.iter().filter(|gap| !gap.text.is_empty()).count();
+        let splits = text
+            .char_indices()
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        for &split in splits.iter() {
+            let (before, word) = text.split_at(split);
+            if !self.affords(needed + 1 + usize::from(!before.is_empty())) {
+                continue;
+            }
+            for slot in first_slot..=last_slot {
+                for divisor in self.new_divisors(value) {
+                    self.rules.insert(
+                        slot,
+                        InferredRule {
+                            divisor,
+                            word: String::from(word),
+                        },
+                    );
+                    let found = match before {
+                        "" => self.fill_gaps(position, rest),
+                        _ => self.fill_gap(position, before, first_slot, slot, rest),
+                    };
+                    if found {
+                        return true;
+                    }
+                    self.rules.remove(slot);
+                }
+            }
+        }
+        false
+    }
+
+    /// the divisors which could first fire for `value`: they divide it, but
+    /// none of the values before it, nor any value printed as a number.
+    /// Zero is visited last, and a rule first firing there fires for no other
+    /// value, so the smallest such divisor is as good as any.
+    fn new_divisors(&self, value: u64) -> Vec<u64> {
+        if value == 0 {
+            let fresh = (2..).find(|&divisor| {
+                self.last / divisor * divisor < self.first
+                    && self.rules.iter().all(|rule| rule.divisor != divisor)
+            });
+            return fresh.into_iter().collect();
+        }
+        // a divisor no larger than `value - first` divides an earlier value too
+        let smallest = value.saturating_sub(self.first) + 1;
+        let mut divisors = Vec::new();
+        let mut small = 1;
+        while small <= value / small {
+            if value.is_multiple_of(small) {
+                divisors.push(small);
+                if small != value / small {
+                    divisors.push(value / small);
+                }
+            }
+            small += 1;
+        }
+        divisors.sort_unstable();
+        divisors.retain(|&divisor| {
+            divisor >= smallest
+                && self.rules.iter().all(|rule| rule.divisor != divisor)
+                && self.numbers.iter().all(|n| !n.is_multiple_of(divisor))
+        });
+        divisors
+    }
+}
+
+/// find the smallest set of divisibility rules which produces `sample`, the
+/// outputs for the consecutive values from `start`, when the words of the
+/// rules which fire are concatenated in order.
+///
+/// Rules which never fire in the sample cannot be inferred, so a longer
+/// sample gives a more faithful rule set. Among rule sets of the same size,
+/// smaller divisors are preferred.
+pub fn infer<S: AsRef<str>>(sample: &[S], start: u64) -> Result<Inference, InferenceError> {
+    let mut search = Search {
+        words: Vec::new(),
+        numbers: Vec::new(),
+        zero: None,
+        first: start.max(1),
+        last: (start + sample.len() as u64).saturating_sub(1),
+        rules: Vec::new(),
+        budget: 0,
+        limited: false,
+        furthest: 0,
+    };
+    for (value, output) in (start..).zip(sample.iter().map(AsRef::as_ref)) {
+        if value == 0 && output == "0" {
+            search.zero = Some(output);
+        } else if output == value.to_string() {
+            search.numbers.push(value);
+        } else if output.is_empty() {
+            return Err(InferenceError::EmptyOutput { value });
+        } else if output.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(InferenceError::WrongNumber {
+                value,
+                found: String::from(output),
+            });
+        } else if value == 0 {
+            search.zero = Some(output);
+        } else {
+            search.words.push((value, output));
+        }
+    }
+
+    // try ever larger rule sets, until one is found or the budget was not
+    // what stopped the search
+    let mut found = false;
+    for budget in 0.. {
+        search.budget = budget;
+        search.limited = false;
+        found = search.search(0);
+        if found || !search.limited {
+            break;
+        }
+    }
+    if !found {
+        // the search only fails on an output it has reached
+        let (value, output) = search.entry(search.furthest).unwrap_or_default();
+        return Err(InferenceError::NoRuleSet {
+            value,
+            output: String::from(output),
+        });
+    }
+
+    let fizzy = search.rules.iter().fold(Fizzy::new(), |fizzy, rule| {
+        fizzy.add_matcher(Matcher::divisible_by(rule.divisor, rule.word.clone()))
+    });
+    Ok(Inference {
+        fizzy,
+        rules: search.rules,
+    })
+}
@@ -1,78 +1,598 @@
-use std::fmt::Display;
+use std::fmt::{self, Display};
+use std::io;
 use std::ops::{Add, Rem};
+use std::sync::Arc;
+
+mod analysis;
+mod compiled;
+pub mod expr;
+pub mod fallback;
+mod format;
+mod history;
+mod infer;
+mod locale;
+mod macros;
+#[cfg(feature = "rayon")]
+mod parallel;
+pub mod predicates;
+mod rules;
+mod stats;
+#[cfg(feature = "futures")]
+mod stream;
+
+pub use analysis::{Analysis, AnalysisError, Implication, OutputDensity};
+pub use compiled::Compiled;
+pub use format::Casing;
+pub use history::History;
+pub use infer::{infer, Inference, InferenceError, InferredRule};
+pub use locale::{Catalog, CatalogError};
+#[cfg(feature = "bigint")]
+pub use num_bigint::BigUint;
+pub use num_traits::FromPrimitive;
+pub use rules::{Rule, RuleSet, RuleSetError, WordRule};
+pub use stats::{OutputCount, Run, Stats, StatsError};
+
+type MatchFn<T> = Arc<dyn Fn(&T, &History) -> bool + Send + Sync>;
+type FallbackFn<T> = Arc<dyn Fn(&T) -> String + Send + Sync>;
+
+/// What is known about the shape of a matcher's function, so that rule sets
+/// made only of divisibility checks can be analysed and compiled.
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    DivisibleBy(u64),
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    /// an arbitrary function
+    Opaque,
+    /// a function which also depends on the position and earlier outputs
+    Stateful,
+}
+
+impl Condition {
+    /// every divisor the condition depends on, or None for an opaque condition
+    fn divisors(&self, divisors: &mut Vec<u64>) -> Option<()> {
+        match self {
+            Condition::DivisibleBy(divisor) => divisors.push(*divisor),
+            Condition::Not(inner) => inner.divisors(divisors)?,
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.divisors(divisors)?;
+                right.divisors(divisors)?
+            }
+            Condition::Opaque | Condition::Stateful => return None,
+        }
+        Some(())
+    }
+
+    /// evaluate the condition on a residue; only meaningful for conditions
+    /// whose divisors all divide the modulus of the residue
+    fn holds(&self, residue: u64) -> bool {
+        match self {
+            Condition::DivisibleBy(divisor) => residue.is_multiple_of(*divisor),
+            Condition::Not(inner) => !inner.holds(residue),
+            Condition::And(left, right) => left.holds(residue) && right.holds(residue),
+            Condition::Or(left, right) => left.holds(residue) || right.holds(residue),
+            Condition::Opaque | Condition::Stateful => false,
+        }
+    }
+
+    fn is_stateful(&self) -> bool {
+        match self {
+            Condition::Stateful => true,
+            Condition::Not(inner) => inner.is_stateful(),
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.is_stateful() || right.is_stateful()
+            }
+            Condition::DivisibleBy(_) | Condition::Opaque => false,
+        }
+    }
+}
+
+/// The arithmetic behind the divisibility helpers.
+///
+/// It is implemented for every type which can be built from a `u8`, converted
+/// from other numbers with `num_traits::FromPrimitive`, and divided with a
+/// remainder by reference, which covers the primitive numbers as well as big
+/// integers such as `num_bigint::BigUint`.
+pub trait Divisible: Sized {
+    /// `divisor` in the representation of the element type, or None if the
+    /// type cannot represent it
+    fn from_divisor(divisor: u64) -> Option<Self>;
+
+    /// whether `self` is a multiple of `divisor`
+    fn has_factor(&self, divisor: &Self) -> bool;
+}
+
+impl<T> Divisible for T
+where
+    T: FromPrimitive + From<u8> + PartialEq,
+    for<'a> &'a T: Rem<&'a T, Output = T>,
+{
+    fn from_divisor(divisor: u64) -> Option<Self> {
+        T::from_u64(divisor)
+    }
+
+    fn has_factor(&self, divisor: &Self) -> bool {
+        // by reference, so that big integers are not cloned for every check
+        self % divisor == 0.into()
+    }
+}
 
 /// A Matcher is a single rule of fizzbuzz: given a function on T, should
 /// a word be substituted in? If yes, which word?
+///
+/// Matchers are cheap to clone: clones share the same match function.
 pub struct Matcher<T> {
-    match_fn: fn(T) -> bool,
-    substitute: &'static str,
+    match_fn: MatchFn<T>,
+    condition: Condition,
+    substitute: String,
+    priority: i32,
+    suppresses: Vec<String>,
+    /// the rule this matcher was made from, for `RuleSet::from_fizzy`
+    rule: Option<Rule>,
+}
+
+impl<T> Matcher<T>
+where
+    T: Clone,
+{
+    pub fn new<F, S>(match_fn: F, substitute: S) -> Matcher<T>
+    where
+        F: 'static + Fn(T) -> bool + Send + Sync,
+        S: Into<String>,
+    {
+        Matcher::new_ref(move |n: &T| match_fn(n.clone()), substitute)
+    }
 }
 
 impl<T> Matcher<T> {
-    pub fn new(match_fn: fn(T) -> bool, substitute: &'static str) -> Matcher<T> {
+    /// like `Matcher::new`, but the function borrows each element, which
+    /// avoids a clone per check for types such as big integers
+    pub fn new_ref<F, S>(match_fn: F, substitute: S) -> Matcher<T>
+    where
+        F: 'static + Fn(&T) -> bool + Send + Sync,
+        S: Into<String>,
+    {
         Matcher {
-            match_fn,
-            substitute,
+            match_fn: Arc::new(move |n, _| match_fn(n)),
+            condition: Condition::Opaque,
+            substitute: substitute.into(),
+            priority: 0,
+            suppresses: Vec::new(),
+            rule: None,
+        }
+    }
+
+    /// a matcher which also sees the position of the element and what was
+    /// output before it, e.g. to fire only after a number was printed.
+    ///
+    /// `Fizzy::evaluate` treats its value as the first element of a sequence.
+    pub fn stateful<F, S>(match_fn: F, substitute: S) -> Matcher<T>
+    where
+        F: 'static + Fn(&T, &History) -> bool + Send + Sync,
+        S: Into<String>,
+    {
+        Matcher {
+            match_fn: Arc::new(match_fn),
+            condition: Condition::Stateful,
+            ..Matcher::new_ref(|_: &T| false, substitute)
+        }
+    }
+
+    /// matchers with a higher priority are evaluated first; matchers with
+    /// equal priority keep the order in which they were added
+    pub fn with_priority(self, priority: i32) -> Self {
+        Matcher { priority, ..self }
+    }
+
+    /// with `Strategy::Suppress`, a firing matcher silences every matcher
+    /// evaluated after it whose substitute is `word`
+    pub fn suppresses<S: Into<String>>(self, word: S) -> Self {
+        let mut suppresses = self.suppresses;
+        suppresses.push(word.into());
+        Matcher { suppresses, ..self }
+    }
+}
+
+impl<T> fmt::Debug for Matcher<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Matcher")
+            .field("substitute", &self.substitute)
+            .field("priority", &self.priority)
+            .field("suppresses", &self.suppresses)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> Clone for Matcher<T> {
+    fn clone(&self) -> Self {
+        Matcher {
+            match_fn: Arc::clone(&self.match_fn),
+            condition: self.condition.clone(),
+            substitute: self.substitute.clone(),
+            priority: self.priority,
+            suppresses: self.suppresses.clone(),
+            rule: self.rule.clone(),
+        }
+    }
+}
+
+impl<T> Matcher<T>
+where
+    T: 'static,
+{
+    /// fire only when both matchers fire, keeping the substitute of `self`
+    pub fn and(self, other: Matcher<T>) -> Self {
+        let (left, right) = (self.match_fn, other.match_fn);
+        Matcher {
+            match_fn: Arc::new(move |n, history| left(n, history) && right(n, history)),
+            condition: Condition::And(Box::new(self.condition), Box::new(other.condition)),
+            rule: None,
+            ..self
+        }
+    }
+
+    /// fire when either matcher fires, keeping the substitute of `self`
+    pub fn or(self, other: Matcher<T>) -> Self {
+        let (left, right) = (self.match_fn, other.match_fn);
+        Matcher {
+            match_fn: Arc::new(move |n, history| left(n, history) || right(n, history)),
+            condition: Condition::Or(Box::new(self.condition), Box::new(other.condition)),
+            rule: None,
+            ..self
+        }
+    }
+
+    /// fire exactly when this matcher would not
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        let inner = self.match_fn;
+        Matcher {
+            match_fn: Arc::new(move |n, history| !inner(n, history)),
+            condition: Condition::Not(Box::new(self.condition)),
+            rule: None,
+            ..self
+        }
+    }
+}
+
+impl<T> Matcher<T>
+where
+    T: 'static + Divisible + Send + Sync,
+{
+    /// substitute `substitute` for every multiple of `divisor`.
+    ///
+    /// Unlike an equivalent `Matcher::new`, the Fizzy knows what this matcher
+    /// checks, which lets `Fizzy::compile` turn it into a lookup table.
+    ///
+    /// Panics if `divisor` is zero or does not fit in T, such as 300 for a u8.
+    pub fn divisible_by<S: Into<String>>(divisor: u64, substitute: S) -> Matcher<T> {
+        assert!(divisor > 0, "cannot match on divisibility by zero");
+        let divisor_t = T::from_divisor(divisor)
+            .unwrap_or_else(|| panic!("{} cannot be represented in the element type", divisor));
+        Matcher {
+            condition: Condition::DivisibleBy(divisor),
+            rule: Some(Rule::Divisor(divisor)),
+            ..Matcher::new_ref(move |n: &T| n.has_factor(&divisor_t), substitute)
+        }
+    }
+}
+
+/// How the matchers of a Fizzy combine into a single output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Strategy {
+    /// join the substitutes of every matcher which fires
+    #[default]
+    Concatenate,
+    /// only the first matcher which fires is used
+    FirstMatch,
+    /// like `Concatenate`, but a firing matcher silences the later
+    /// matchers named by `Matcher::suppresses`
+    Suppress,
+}
+
+/// A matcher which fired for an element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchedRule {
+    /// the position of the matcher, in the order it was added to the Fizzy
+    pub index: usize,
+    pub substitute: String,
+}
+
+/// The result of evaluating a single element: either the element itself,
+/// when no matcher fired, or the matchers which fired in evaluation order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output<T> {
+    Value(T),
+    Words(Vec<MatchedRule>),
+}
+
+impl<T: Display> Display for Output<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Output::Value(value) => write!(f, "{}", value),
+            Output::Words(rules) => rules
+                .iter()
+                .try_for_each(|rule| f.write_str(&rule.substitute)),
         }
     }
 }
 
 /// A Fizzy is a set of matchers, which may be applied to an iterator.
+///
+/// Applying a Fizzy only borrows it, so one rule set can be reused, cloned,
+/// or shared between threads.
 pub struct Fizzy<T> {
     matchers: Vec<Matcher<T>>,
+    /// indices into `matchers`, in evaluation order
+    order: Vec<usize>,
+    strategy: Strategy,
+    /// formats elements which no matcher fired for; `Display` if None
+    fallback: Option<FallbackFn<T>>,
+    joiner: String,
+    casing: Casing,
+    template: Option<format::Template>,
+    /// whether any matcher needs a `History`
+    stateful: bool,
+    catalog: Option<Arc<Catalog>>,
+    locale: Option<String>,
+    /// the translated substitutes, by matcher, once a locale is chosen
+    words: Vec<String>,
+}
+
+impl<T> Clone for Fizzy<T> {
+    fn clone(&self) -> Self {
+        Fizzy {
+            matchers: self.matchers.clone(),
+            order: self.order.clone(),
+            strategy: self.strategy,
+            fallback: self.fallback.clone(),
+            joiner: self.joiner.clone(),
+            casing: self.casing,
+            template: self.template.clone(),
+            stateful: self.stateful,
+            catalog: self.catalog.clone(),
+            locale: self.locale.clone(),
+            words: self.words.clone(),
+        }
+    }
 }
 
 impl<T> Fizzy<T>
 where
-    T: 'static + Display + Add<Output = T> + Rem<Output = T> + From<u8> + PartialEq + Copy,
+    T: Divisible,
 {
     pub fn is_divisible_by(element: &T, number: u8) -> bool {
-        let result = *element % number.into();
-        result == 0.into()
-    }
-    fn run_matchers(matchers: &Vec<Matcher<T>>, element: T) -> Option<String> {
-        matchers.iter().fold(None, |acc, matcher| {
-            match (acc, (matcher.match_fn)(element)) {
-                (Some(acc_val), true) => Some(format!("{}{}", acc_val, matcher.substitute)),
-                (Some(acc_val), false) => Some(acc_val),
-                (None, true) => Some(String::from(matcher.substitute)),
-                (None, false) => None,
+        T::from_divisor(number.into()).is_some_and(|number| element.has_factor(&number))
+    }
+}
+
+impl<T> Fizzy<T> {
+    /// collect the indices of the matchers which fire for `element` into
+    /// `fired`, in evaluation order; the buffer is reused so that evaluation
+    /// does not allocate
+    fn collect_fired(&self, element: &T, history: &History, fired: &mut Vec<usize>) {
+        self.collect_fired_by(fired, |matcher| (matcher.match_fn)(element, history))
+    }
+
+    fn collect_fired_by<F>(&self, fired: &mut Vec<usize>, fires: F)
+    where
+        F: Fn(&Matcher<T>) -> bool,
+    {
+        fired.clear();
+        for &index in self.order.iter() {
+            let matcher = &self.matchers[index];
+            let suppressed = self.strategy == Strategy::Suppress
+                && fired.iter().any(|&earlier| {
+                    self.matchers[earlier]
+                        .suppresses
+                        .contains(&matcher.substitute)
+                });
+            if suppressed || !fires(matcher) {
+                continue;
             }
-        })
+            fired.push(index);
+            if self.strategy == Strategy::FirstMatch {
+                break;
+            }
+        }
     }
 
     pub fn new() -> Self {
         Fizzy {
             matchers: Vec::new(),
+            order: Vec::new(),
+            strategy: Strategy::default(),
+            fallback: None,
+            joiner: String::new(),
+            casing: Casing::default(),
+            template: None,
+            stateful: false,
+            catalog: None,
+            locale: None,
+            words: Vec::new(),
         }
     }
 
-    pub fn add_matcher(self, matcher: Matcher<T>) -> Self {
-        let mut matchers = self.matchers;
-        matchers.push(matcher);
-        Fizzy { matchers }
+    pub fn add_matcher(mut self, matcher: Matcher<T>) -> Self {
+        let matchers = &self.matchers;
+        let position = self
+            .order
+            .partition_point(|&index| matchers[index].priority >= matcher.priority);
+        self.order.insert(position, matchers.len());
+        self.stateful |= matcher.condition.is_stateful();
+        self.matchers.push(matcher);
+        self.translate_added();
+        self
+    }
+
+    /// choose how the matchers are combined; the default is `Strategy::Concatenate`
+    pub fn with_strategy(self, strategy: Strategy) -> Self {
+        Fizzy { strategy, ..self }
     }
 
+    /// format elements which no matcher fired for with `fallback` instead of
+    /// `Display`; the `fallback` module has the common formatters
+    pub fn with_fallback<F>(self, fallback: F) -> Self
+    where
+        F: 'static + Fn(&T) -> String + Send + Sync,
+    {
+        Fizzy {
+            fallback: Some(Arc::new(fallback)),
+            ..self
+        }
+    }
+}
+
+impl<T> Fizzy<T>
+where
+    T: Display,
+{
     /// map this fizzy onto every element of an iterator, returning a new iterator
-    pub fn apply<I>(self, iter: I) -> impl Iterator<Item = String>
+    pub fn apply<'a, I>(&'a self, iter: I) -> impl Iterator<Item = String> + 'a
+    where
+        I: Iterator<Item = T> + 'a,
+    {
+        let (mut fired, mut history) = (Vec::new(), History::default());
+        iter.map(move |n| self.evaluate_next(&n, &mut history, &mut fired))
+    }
+
+    /// the output for the element after `history`, moving the history on
+    fn evaluate_next(&self, value: &T, history: &mut History, fired: &mut Vec<usize>) -> String {
+        self.collect_fired(value, history, fired);
+        self.record(history, fired);
+        self.format_output(value, fired)
+    }
+
+    /// the output for a single value, without walking any other values
+    pub fn evaluate(&self, value: T) -> String {
+        let mut fired = Vec::new();
+        self.collect_fired(&value, &History::default(), &mut fired);
+        self.format_output(&value, &fired)
+    }
+
+    /// like `evaluate`, but tells apart a value from matched words and
+    /// reports which matchers fired
+    pub fn evaluate_detailed(&self, value: T) -> Output<T> {
+        let mut fired = Vec::new();
+        self.collect_fired(&value, &History::default(), &mut fired);
+        self.detail_fired(value, &fired)
+    }
+
+    fn detail_fired(&self, value: T, fired: &[usize]) -> Output<T> {
+        match fired {
+            [] => Output::Value(value),
+            indices => Output::Words(
+                indices
+                    .iter()
+                    .map(|&index| MatchedRule {
+                        index,
+                        substitute: String::from(self.word(index)),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// map this fizzy onto every element of an iterator, like `apply`, but
+    /// yielding the detailed output of `evaluate_detailed`
+    pub fn apply_detailed<'a, I>(&'a self, iter: I) -> impl Iterator<Item = Output<T>> + 'a
+    where
+        I: Iterator<Item = T> + 'a,
+    {
+        let (mut fired, mut history) = (Vec::new(), History::default());
+        iter.map(move |n| {
+            self.collect_fired(&n, &history, &mut fired);
+            self.record(&mut history, &fired);
+            self.detail_fired(n, &fired)
+        })
+    }
+
+    /// the outputs for every value in `[start, end)`, starting directly at `start`
+    pub fn range(&self, start: T, end: T) -> impl Iterator<Item = String> + '_
+    where
+        T: Clone + Add<Output = T> + From<u8> + PartialOrd,
+    {
+        self.apply(values_between(start, end))
+    }
+
+    /// write the output for every element of an iterator into `writer`, with
+    /// `separator` between elements, without allocating a String per element
+    /// unless a fallback formatter is used
+    pub fn apply_to_fmt_writer<I, W>(&self, iter: I, writer: &mut W, separator: &str) -> fmt::Result
     where
         I: Iterator<Item = T>,
+        W: fmt::Write,
     {
-        let matchers = self.matchers;
+        let (mut fired, mut history) = (Vec::new(), History::default());
+        for n in iter {
+            if history.index() > 0 {
+                writer.write_str(separator)?;
+            }
+            self.collect_fired(&n, &history, &mut fired);
+            self.record(&mut history, &fired);
+            self.write_output(&n, &fired, writer)?;
+        }
+        Ok(())
+    }
+
+    /// like `apply_to_fmt_writer`, but for byte-oriented writers such as files
+    /// or stdout. Wrap unbuffered writers in a `BufWriter`.
+    pub fn apply_to_writer<I, W>(&self, iter: I, writer: &mut W, separator: &str) -> io::Result<()>
+    where
+        I: Iterator<Item = T>,
+        W: io::Write,
+    {
+        let mut adapter = IoAdapter {
+            inner: writer,
+            error: None,
+        };
+        match self.apply_to_fmt_writer(iter, &mut adapter, separator) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(adapter
+                .error
+                .unwrap_or_else(|| io::Error::other("formatter error"))),
+        }
+    }
+}
 
-        iter.map(move |n| match Fizzy::run_matchers(&matchers, n) {
-            None => n.to_string(),
-            Some(val) => val,
+/// count upwards from `start` to just before `end`
+fn values_between<T>(start: T, end: T) -> impl Iterator<Item = T>
+where
+    T: Clone + Add<Output = T> + From<u8> + PartialOrd,
+{
+    std::iter::successors(Some(start), |n| Some(n.clone() + 1.into())).take_while(move |n| *n < end)
+}
+
+/// lets the fmt::Write based writer feed an io::Write, keeping the io error
+struct IoAdapter<'w, W> {
+    inner: &'w mut W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
         })
     }
 }
 
+impl<T> Default for Fizzy<T> {
+    fn default() -> Self {
+        Fizzy::new()
+    }
+}
+
 /// convenience function: return a Fizzy which applies the standard fizz-buzz rules
 pub fn fizz_buzz<T>() -> Fizzy<T>
 where
-    T: 'static + Display + Add<Output = T> + Rem<Output = T> + From<u8> + PartialEq + Copy,
+    T: 'static + Divisible + Send + Sync,
 {
     Fizzy::new()
-        .add_matcher(Matcher::new(|n| Fizzy::is_divisible_by(&n, 3), "fizz"))
-        .add_matcher(Matcher::new(|n| Fizzy::is_divisible_by(&n, 5), "buzz"))
+        .add_matcher(Matcher::divisible_by(3, "fizz"))
+        .add_matcher(Matcher::divisible_by(5, "buzz"))
 }
@@ -0,0 +1,211 @@
+//! Translations of the substitutes, so that one rule set can be played in
+//! several languages.
+//!
+//! The substitute of every matcher is a key, which `Fizzy::in_locale`
+//! resolves through a `Catalog`. A catalog file holds one locale, with a
+//! `key = word` pair per line; blank lines and lines starting with `#` are
+//! ignored:
+//!
+//! ```text
+//! # German
+//! fizz = brum
+//! buzz = summ
+//! ```
+
+use crate::Fizzy;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// the extension of the files which `Catalog::load_dir` reads
+const EXTENSION: &str = "catalog";
+
+/// Why a catalog could not be loaded or used.
+#[derive(Debug)]
+pub enum CatalogError {
+    Io(PathBuf, io::Error),
+    /// a line of a catalog, counting from 1, is not a `key = word` pair
+    Syntax {
+        locale: String,
+        line: usize,
+    },
+    /// the locale is not in the catalog
+    UnknownLocale(String),
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CatalogError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+            CatalogError::Syntax { locale, line } => write!(
+                f,
+                "line {} of the {} catalog: expected `key = word`",
+                line, locale
+            ),
+            CatalogError::UnknownLocale(locale) => {
+                write!(f, "no catalog for the locale {}", locale)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CatalogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CatalogError::Io(_, err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Words for each locale, looked up by key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Catalog {
+    locales: BTreeMap<String, HashMap<String, String>>,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Catalog::default()
+    }
+
+    /// translate `key` as `word` in `locale`, replacing any earlier word
+    pub fn with_word<L, K, W>(mut self, locale: L, key: K, word: W) -> Self
+    where
+        L: Into<String>,
+        K: Into<String>,
+        W: Into<String>,
+    {
+        self.locales
+            .entry(locale.into())
+            .or_default()
+            .insert(key.into(), word.into());
+        self
+    }
+
+    /// add the `key = word` lines of `text` to `locale`
+    pub fn with_entries(self, locale: &str, text: &str) -> Result<Self, CatalogError> {
+        let mut catalog = self;
+        catalog.locales.entry(String::from(locale)).or_default();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, word) = line
+                .split_once('=')
+                .map(|(key, word)| (key.trim(), word.trim()))
+                .filter(|(key, _)| !key.is_empty())
+                .ok_or_else(|| CatalogError::Syntax {
+                    locale: String::from(locale),
+                    line: index + 1,
+                })?;
+            catalog = catalog.with_word(locale, key, word);
+        }
+        Ok(catalog)
+    }
+
+    /// add the catalog file at `path` as `locale`
+    pub fn load_file<P: AsRef<Path>>(self, locale: &str, path: P) -> Result<Self, CatalogError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|err| CatalogError::Io(path.into(), err))?;
+        self.with_entries(locale, &text)
+    }
+
+    /// load every `.catalog` file in `dir`, named after its locale, such as
+    /// `de.catalog`
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> Result<Self, CatalogError> {
+        let dir = dir.as_ref();
+        let io_error = |err| CatalogError::Io(dir.into(), err);
+        let mut catalog = Catalog::new();
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path
+                .extension()
+                .is_none_or(|extension| extension != EXTENSION)
+            {
+                continue;
+            }
+            if let Some(locale) = path.file_stem().and_then(|stem| stem.to_str()) {
+                catalog = catalog.load_file(locale, &path)?;
+            }
+        }
+        Ok(catalog)
+    }
+
+    /// the locales in the catalog, in alphabetical order
+    pub fn locales(&self) -> impl Iterator<Item = &str> {
+        self.locales.keys().map(String::as_str)
+    }
+
+    /// the word for `key` in `locale`, if the catalog has one
+    pub fn translate(&self, locale: &str, key: &str) -> Option<&str> {
+        self.locales
+            .get(locale)
+            .and_then(|words| words.get(key))
+            .map(String::as_str)
+    }
+}
+
+impl<T> Fizzy<T> {
+    /// resolve substitutes through `catalog` once a locale is chosen with
+    /// `in_locale`; any locale chosen before is dropped
+    pub fn with_catalog(self, catalog: Catalog) -> Self {
+        Fizzy {
+            catalog: Some(Arc::new(catalog)),
+            locale: None,
+            words: Vec::new(),
+            ..self
+        }
+    }
+
+    /// a copy of this fizzy which outputs the words of `locale`; substitutes
+    /// without a translation are output as they are. Suppression still names
+    /// the untranslated substitutes.
+    pub fn in_locale(&self, locale: &str) -> Result<Fizzy<T>, CatalogError> {
+        let catalog = self
+            .catalog
+            .as_ref()
+            .filter(|catalog| catalog.locales.contains_key(locale))
+            .ok_or_else(|| CatalogError::UnknownLocale(String::from(locale)))?;
+        let words = self
+            .matchers
+            .iter()
+            .map(|matcher| translate(catalog, locale, &matcher.substitute))
+            .collect();
+        Ok(Fizzy {
+            locale: Some(String::from(locale)),
+            words,
+            ..self.clone()
+        })
+    }
+
+    /// the locale chosen with `in_locale`, if any
+    pub fn locale(&self) -> Option<&str> {
+        self.locale.as_deref()
+    }
+
+    /// the word output for the matcher at `index`
+    pub(crate) fn word(&self, index: usize) -> &str {
+        match self.words.get(index) {
+            Some(word) => word,
+            None => &self.matchers[index].substitute,
+        }
+    }
+
+    /// translate the substitute of a matcher added after the locale was chosen
+    pub(crate) fn translate_added(&mut self) {
+        if let (Some(catalog), Some(locale)) = (&self.catalog, &self.locale) {
+            let key = &self.matchers[self.words.len()].substitute;
+            let word = translate(catalog, locale, key);
+            self.words.push(word);
+        }
+    }
+}
+
+fn translate(catalog: &Catalog, locale: &str, key: &str) -> String {
+    String::from(catalog.translate(locale, key).unwrap_or(key))
+}
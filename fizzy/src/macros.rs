@@ -0,0 +1,76 @@
+/// Build a Fizzy from a list of rules, such as
+/// `fizzy! { 3 => "fizz", 5 => "buzz", contains(7) => "bang" }`.
+///
+/// Every rule is `CONDITION => WORD`, where the word is any expression which
+/// converts into a String and the condition is one of:
+///
+/// - an integer literal, for the multiples of that number
+/// - `contains(DIGIT)`, for elements containing a decimal digit
+/// - `digit_sum(DIVISOR)`, for elements whose digits sum to a multiple
+/// - `prime`, `palindrome` or `square`
+/// - `in(RANGE)`, for elements within a range
+/// - a closure `|n| ...` taking the element by value
+///
+/// The rules become `add_matcher` calls in the order they are written, and
+/// the element type is inferred from how the Fizzy is used. Divisors of zero
+/// and digits above 9 are rejected at compile time.
+#[macro_export]
+macro_rules! fizzy {
+    (@rules $fizzy:expr;) => {
+        $fizzy
+    };
+    (@rules $fizzy:expr; $divisor:literal => $word:expr $(, $($rest:tt)*)?) => {
+        $crate::fizzy!(@rules $fizzy.add_matcher({
+            const _: () = assert!($divisor > 0, "fizzy!: cannot match on divisibility by zero");
+            $crate::Matcher::divisible_by($divisor, $word)
+        }); $($($rest)*)?)
+    };
+    (@rules $fizzy:expr; contains($digit:literal) => $word:expr $(, $($rest:tt)*)?) => {
+        $crate::fizzy!(@rules $fizzy.add_matcher({
+            const _: () = assert!($digit < 10, "fizzy!: contains(..) takes a decimal digit");
+            $crate::predicates::contains_digit($digit, $word)
+        }); $($($rest)*)?)
+    };
+    (@rules $fizzy:expr; digit_sum($divisor:literal) => $word:expr $(, $($rest:tt)*)?) => {
+        $crate::fizzy!(@rules $fizzy.add_matcher({
+            const _: () = assert!($divisor > 0, "fizzy!: cannot match on divisibility by zero");
+            $crate::predicates::digit_sum_divisible_by($divisor, $word)
+        }); $($($rest)*)?)
+    };
+    (@rules $fizzy:expr; prime => $word:expr $(, $($rest:tt)*)?) => {
+        $crate::fizzy!(@rules $fizzy.add_matcher(
+            $crate::predicates::is_prime($word)
+        ); $($($rest)*)?)
+    };
+    (@rules $fizzy:expr; palindrome => $word:expr $(, $($rest:tt)*)?) => {
+        $crate::fizzy!(@rules $fizzy.add_matcher(
+            $crate::predicates::is_palindrome($word)
+        ); $($($rest)*)?)
+    };
+    (@rules $fizzy:expr; square => $word:expr $(, $($rest:tt)*)?) => {
+        $crate::fizzy!(@rules $fizzy.add_matcher(
+            $crate::predicates::is_perfect_square($word)
+        ); $($($rest)*)?)
+    };
+    (@rules $fizzy:expr; in($range:expr) => $word:expr $(, $($rest:tt)*)?) => {
+        $crate::fizzy!(@rules $fizzy.add_matcher(
+            $crate::predicates::in_range($range, $word)
+        ); $($($rest)*)?)
+    };
+    (@rules $fizzy:expr; |$n:ident| $body:expr => $word:expr $(, $($rest:tt)*)?) => {
+        $crate::fizzy!(@rules $fizzy.add_matcher(
+            $crate::Matcher::new(move |$n| $body, $word)
+        ); $($($rest)*)?)
+    };
+    (@rules $fizzy:expr; $($bad:tt)*) => {
+        compile_error!(concat!(
+            "fizzy!: expected a rule such as `3 => \"fizz\"`, `contains(7) => \"bang\"` ",
+            "or `|n| n > 100 => \"big\"`, separated by commas, but found `",
+            stringify!($($bad)*),
+            "`"
+        ))
+    };
+    ($($rules:tt)*) => {
+        $crate::fizzy!(@rules $crate::Fizzy::new(); $($rules)*)
+    };
+}
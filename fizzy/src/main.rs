@@ -0,0 +1,230 @@
+use fizzy::{fizz_buzz, Fizzy, Matcher};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::process;
+
+const USAGE: &str = "usage: fizzy [OPTIONS] <RANGE>
+
+RANGE is one of `N` (1..=N), `A..B` or `A..=B`.
+
+options:
+    --rule K=WORD        say WORD for numbers divisible by K (repeatable)
+    --rules FILE         read K=WORD rules from FILE, one per line
+    --separator SEP      print every output on one line, separated by SEP
+    --lines              print one output per line (default)
+    --json               print the outputs as a JSON array of strings
+    -h, --help           print this message
+
+without any rules the standard fizz-buzz rules are used";
+
+#[derive(Debug)]
+enum Output {
+    Lines,
+    Separated(String),
+    Json,
+}
+
+#[derive(Debug)]
+struct Options {
+    start: u64,
+    end: u64,
+    rules: Vec<(u64, String)>,
+    output: Output,
+}
+
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    /// the rules file could not be read
+    File {
+        file_name: String,
+        source: io::Error,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}\n\n{}", message, USAGE),
+            CliError::File { file_name, source } => write!(f, "{}: {}", file_name, source),
+            CliError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(err: io::Error) -> Self {
+        CliError::Io(err)
+    }
+}
+
+fn usage_error<T>(message: String) -> Result<T, CliError> {
+    Err(CliError::Usage(message))
+}
+
+fn parse_number(text: &str) -> Result<u64, CliError> {
+    text.trim()
+        .parse()
+        .or_else(|_| usage_error(format!("invalid number: '{}'", text)))
+}
+
+/// parse `N`, `A..B` or `A..=B` into an inclusive `(start, end)` pair,
+/// where an empty range has `start > end`
+fn parse_range(text: &str) -> Result<(u64, u64), CliError> {
+    if let Some((start, end)) = text.split_once("..=") {
+        Ok((parse_number(start)?, parse_number(end)?))
+    } else if let Some((start, end)) = text.split_once("..") {
+        let end = parse_number(end)?;
+        match end.checked_sub(1) {
+            Some(last) => Ok((parse_number(start)?, last)),
+            None => Ok((1, 0)),
+        }
+    } else {
+        Ok((1, parse_number(text)?))
+    }
+}
+
+fn parse_rule(text: &str) -> Result<(u64, String), CliError> {
+    let (divisor, word) = match text.split_once('=') {
+        Some(pair) => pair,
+        None => return usage_error(format!("rule must look like K=WORD: '{}'", text)),
+    };
+    match parse_number(divisor)? {
+        0 => usage_error(format!("rule divisor must not be zero: '{}'", text)),
+        divisor => Ok((divisor, String::from(word))),
+    }
+}
+
+fn read_rules_file(file_name: &str) -> Result<Vec<(u64, String)>, CliError> {
+    fs::read_to_string(file_name)
+        .map_err(|source| CliError::File {
+            file_name: String::from(file_name),
+            source,
+        })?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(parse_rule)
+        .collect()
+}
+
+fn parse_args<I>(mut args: I) -> Result<Option<Options>, CliError>
+where
+    I: Iterator<Item = String>,
+{
+    let mut range = None;
+    let mut rules = Vec::new();
+    let mut output = Output::Lines;
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .map_or_else(|| usage_error(format!("{} needs a value", flag)), Ok)
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "--rule" => rules.push(parse_rule(&value("--rule")?)?),
+            "--rules" => rules.extend(read_rules_file(&value("--rules")?)?),
+            "--separator" => output = Output::Separated(value("--separator")?),
+            "--lines" => output = Output::Lines,
+            "--json" => output = Output::Json,
+            flag if flag.starts_with("--") => {
+                return usage_error(format!("unknown option: '{}'", flag))
+            }
+            _ if range.is_some() => return usage_error(format!("unexpected argument: '{}'", arg)),
+            _ => range = Some(parse_range(&arg)?),
+        }
+    }
+
+    match range {
+        None => usage_error(String::from("missing RANGE")),
+        Some((start, end)) => Ok(Some(Options {
+            start,
+            end,
+            rules,
+            output,
+        })),
+    }
+}
+
+fn build_fizzy(rules: Vec<(u64, String)>) -> Fizzy<u64> {
+    if rules.is_empty() {
+        return fizz_buzz();
+    }
+    rules
+        .into_iter()
+        .fold(Fizzy::new(), |fizzy, (divisor, word)| {
+            fizzy.add_matcher(Matcher::divisible_by(divisor, word))
+        })
+}
+
+fn write_json_string(out: &mut impl Write, text: &str) -> io::Result<()> {
+    out.write_all(b"\"")?;
+    for c in text.chars() {
+        match c {
+            '"' => out.write_all(b"\\\"")?,
+            '\\' => out.write_all(b"\\\\")?,
+            '\n' => out.write_all(b"\\n")?,
+            '\r' => out.write_all(b"\\r")?,
+            '\t' => out.write_all(b"\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    out.write_all(b"\"")
+}
+
+fn run(options: Options) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let fizzy = build_fizzy(options.rules);
+    let range = options.start..=options.end;
+
+    match options.output {
+        Output::Lines | Output::Separated(_) if range.is_empty() => (),
+        Output::Lines => {
+            fizzy.apply_to_writer(range, &mut out, "\n")?;
+            writeln!(out)?;
+        }
+        Output::Separated(separator) => {
+            fizzy.apply_to_writer(range, &mut out, &separator)?;
+            writeln!(out)?;
+        }
+        Output::Json => {
+            out.write_all(b"[")?;
+            for (index, text) in fizzy.apply(range).enumerate() {
+                if index > 0 {
+                    out.write_all(b",")?;
+                }
+                write_json_string(&mut out, &text)?;
+            }
+            writeln!(out, "]")?;
+        }
+    }
+    out.flush()
+}
+
+fn main() {
+    let result = parse_args(env::args().skip(1)).and_then(|options| match options {
+        None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(options) => run(options).map_err(CliError::from),
+    });
+
+    match result {
+        Ok(()) => (),
+        Err(CliError::Io(ref err)) if err.kind() == io::ErrorKind::BrokenPipe => (),
+        Err(err) => {
+            eprintln!("fizzy: {}", err);
+            process::exit(match err {
+                CliError::Usage(_) => 2,
+                CliError::File { .. } | CliError::Io(_) => 1,
+            });
+        }
+    }
+}
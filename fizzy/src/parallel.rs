@@ -0,0 +1,31 @@
+use crate::{Fizzy, History};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+use std::fmt::Display;
+
+impl<T> Fizzy<T>
+where
+    T: Display + Send + Sync,
+{
+    /// like `apply`, but evaluates the elements of an indexed parallel
+    /// iterator, such as a range, on the rayon thread pool. The outputs are
+    /// the same as those of `apply`, in the original order.
+    ///
+    /// Stateful matchers depend on every earlier output, so a Fizzy with any
+    /// is evaluated sequentially after the elements have been collected.
+    pub fn par_apply<I>(&self, iter: I) -> Vec<String>
+    where
+        I: IntoParallelIterator<Item = T>,
+        I::Iter: IndexedParallelIterator,
+    {
+        if self.stateful {
+            let values = iter.into_par_iter().collect::<Vec<_>>();
+            return self.apply(values.into_iter()).collect();
+        }
+        iter.into_par_iter()
+            .map_init(Vec::new, |fired, n| {
+                self.collect_fired(&n, &History::default(), fired);
+                self.format_output(&n, fired)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,206 @@
+//! Ready-made matchers for the common variants of the game.
+//!
+//! Each function takes the word to substitute and returns a `Matcher`, so a
+//! rule such as "fizz if it contains a 3" is a single call:
+//! `predicates::contains_digit(3, "fizz")`.
+//!
+//! The digit-based matchers look at the decimal representation of the
+//! element as printed by `Display`, ignoring any sign.
+
+use crate::{Divisible, Matcher, Rule};
+use std::any::Any;
+use std::convert::{TryFrom, TryInto};
+use std::fmt::Display;
+use std::ops::{Bound, RangeBounds};
+
+/// the decimal digits of `n`, most significant first
+fn digits<T: Display>(n: &T) -> impl Iterator<Item = u8> {
+    n.to_string()
+        .into_bytes()
+        .into_iter()
+        .filter(u8::is_ascii_digit)
+        .map(|c| c - b'0')
+}
+
+/// substitute `substitute` for every multiple of `divisor`
+pub fn divisible_by<T, S>(divisor: u64, substitute: S) -> Matcher<T>
+where
+    T: 'static + Divisible + Send + Sync,
+    S: Into<String>,
+{
+    Matcher::divisible_by(divisor, substitute)
+}
+
+/// substitute `substitute` for every element containing the decimal digit `digit`
+pub fn contains_digit<T, S>(digit: u8, substitute: S) -> Matcher<T>
+where
+    T: Display,
+    S: Into<String>,
+{
+    assert!(digit < 10, "{} is not a decimal digit", digit);
+    Matcher {
+        rule: Some(Rule::Digit(digit)),
+        ..Matcher::new_ref(move |n: &T| digits(n).any(|d| d == digit), substitute)
+    }
+}
+
+/// substitute `substitute` for every element whose decimal digits sum to a multiple of `divisor`
+pub fn digit_sum_divisible_by<T, S>(divisor: u64, substitute: S) -> Matcher<T>
+where
+    T: Display,
+    S: Into<String>,
+{
+    assert!(divisor > 0, "cannot match on divisibility by zero");
+    Matcher::new_ref(
+        move |n: &T| {
+            digits(n)
+                .map(u64::from)
+                .sum::<u64>()
+                .is_multiple_of(divisor)
+        },
+        substitute,
+    )
+}
+
+/// substitute `substitute` for every element whose decimal digits read the same backwards
+pub fn is_palindrome<T, S>(substitute: S) -> Matcher<T>
+where
+    T: Display,
+    S: Into<String>,
+{
+    Matcher::new_ref(
+        |n: &T| {
+            let digits = digits(n).collect::<Vec<_>>();
+            digits.iter().eq(digits.iter().rev())
+        },
+        substitute,
+    )
+}
+
+fn mul_mod(a: u64, b: u64, modulus: u64) -> u64 {
+    (u128::from(a) * u128::from(b) % u128::from(modulus)) as u64
+}
+
+fn pow_mod(mut base: u64, mut exponent: u64, modulus: u64) -> u64 {
+    let mut result = 1;
+    base %= modulus;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, base, modulus);
+        }
+        base = mul_mod(base, base, modulus);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// deterministic Miller-Rabin: these witnesses are enough for every u64
+fn is_prime_u64(n: u64) -> bool {
+    const WITNESSES: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if n < 2 {
+        return false;
+    }
+    if let Some(&p) = WITNESSES.iter().find(|&&p| n.is_multiple_of(p)) {
+        return n == p;
+    }
+    let (mut d, mut s) = (n - 1, 0);
+    while d.is_multiple_of(2) {
+        d /= 2;
+        s += 1;
+    }
+    WITNESSES.iter().all(|&a| {
+        let mut x = pow_mod(a, d, n);
+        if x == 1 || x == n - 1 {
+            return true;
+        }
+        (1..s).any(|_| {
+            x = mul_mod(x, x, n);
+            x == n - 1
+        })
+    })
+}
+
+/// substitute `substitute` for every prime element.
+///
+/// Elements which do not fit in a `u64`, such as negative numbers, never match.
+pub fn is_prime<T, S>(substitute: S) -> Matcher<T>
+where
+    T: Clone + TryInto<u64>,
+    S: Into<String>,
+{
+    Matcher::new_ref(
+        |n: &T| n.clone().try_into().is_ok_and(is_prime_u64),
+        substitute,
+    )
+}
+
+/// substitute `substitute` for every element which is the square of an integer.
+///
+/// Elements which do not fit in a `u64`, such as negative numbers, never match.
+pub fn is_perfect_square<T, S>(substitute: S) -> Matcher<T>
+where
+    T: Clone + TryInto<u64>,
+    S: Into<String>,
+{
+    Matcher::new_ref(
+        |n: &T| {
+            n.clone().try_into().is_ok_and(|n: u64| {
+                let root = n.isqrt();
+                root * root == n
+            })
+        },
+        substitute,
+    )
+}
+
+/// `n` as an i128, if T is a primitive integer
+fn as_integer<T: Any>(n: &T) -> Option<i128> {
+    let n: &dyn Any = n;
+    macro_rules! convert {
+        ($($int:ty),*) => {
+            $(
+                if let Some(&n) = n.downcast_ref::<$int>() {
+                    return i128::try_from(n).ok();
+                }
+            )*
+        };
+    }
+    convert!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+    None
+}
+
+/// `range` as a half-open `Rule::Range`, if it is a bounded range of
+/// primitive integers within the i64 range
+fn range_rule<T: Any, R: RangeBounds<T>>(range: &R) -> Option<Rule> {
+    let start = match range.start_bound() {
+        Bound::Included(start) => as_integer(start)?,
+        Bound::Excluded(start) => as_integer(start)? + 1,
+        Bound::Unbounded => return None,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(end) => as_integer(end)? + 1,
+        Bound::Excluded(end) => as_integer(end)?,
+        Bound::Unbounded => return None,
+    };
+    Some(Rule::Range {
+        start: start.try_into().ok()?,
+        end: end.try_into().ok()?,
+    })
+}
+
+/// substitute `substitute` for every element within `range`, e.g. `in_range(10..20, "teen")`.
+///
+/// A bounded range of integers is recorded as a `Rule::Range`, so that the
+/// matcher can be part of a `RuleSet`.
+pub fn in_range<T, R, S>(range: R, substitute: S) -> Matcher<T>
+where
+    T: 'static + PartialOrd,
+    R: 'static + RangeBounds<T> + Send + Sync,
+    S: Into<String>,
+{
+    let rule = range_rule(&range);
+    Matcher {
+        rule,
+        ..Matcher::new_ref(move |n: &T| range.contains(n), substitute)
+    }
+}
@@ -0,0 +1,176 @@
+//! Rule sets as plain data, which can be stored and loaded with serde when
+//! the `serde` feature is enabled.
+//!
+//! Only the matchers made by `Matcher::divisible_by`,
+//! `predicates::contains_digit`, `predicates::in_range` over integers and the
+//! expression language can be described; closures and combinations of matchers cannot. The
+//! joiner, casing, template, fallback and catalog of a Fizzy are not part of
+//! its rule set.
+
+use crate::expr::{Expr, ParseError};
+use crate::{predicates, Divisible, Fizzy, Matcher, Strategy};
+use std::convert::TryInto;
+use std::fmt::{self, Display};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// What a matcher checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Rule {
+    /// the element is a multiple of the divisor
+    Divisor(u64),
+    /// the element contains the decimal digit
+    Digit(u8),
+    /// the element lies in `start..end`, like `predicates::in_range`
+    Range { start: i64, end: i64 },
+    /// the expression, in the language of the `expr` module, holds
+    Expression(String),
+}
+
+/// A rule and the word it substitutes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WordRule {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub rule: Rule,
+    pub word: String,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_zero"))]
+    pub priority: i32,
+    /// the words this rule silences with `Strategy::Suppress`
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub suppresses: Vec<String>,
+}
+
+#[cfg(feature = "serde")]
+fn is_zero(priority: &i32) -> bool {
+    *priority == 0
+}
+
+/// The matchers of a Fizzy and how they combine, as data.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RuleSet {
+    /// in the order the matchers are added
+    pub rules: Vec<WordRule>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub strategy: Strategy,
+}
+
+/// Why a rule set cannot be made into a Fizzy, or a Fizzy into a rule set.
+/// Rules and matchers are referred to by their index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleSetError {
+    /// the matcher is a closure or a combination of matchers
+    NotRepresentable(usize),
+    ZeroDivisor(usize),
+    /// the divisor does not fit in the element type
+    DivisorTooLarge(usize),
+    NotADigit(usize),
+    Expression(usize, ParseError),
+}
+
+impl Display for RuleSetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleSetError::NotRepresentable(index) => {
+                write!(f, "matcher {} is not made from a rule", index)
+            }
+            RuleSetError::ZeroDivisor(index) => {
+                write!(f, "rule {}: cannot match on divisibility by zero", index)
+            }
+            RuleSetError::DivisorTooLarge(index) => write!(
+                f,
+                "rule {}: the divisor cannot be represented in the element type",
+                index
+            ),
+            RuleSetError::NotADigit(index) => {
+                write!(f, "rule {}: the digit is not a decimal digit", index)
+            }
+            RuleSetError::Expression(index, err) => write!(f, "rule {}: {}", index, err),
+        }
+    }
+}
+
+impl std::error::Error for RuleSetError {}
+
+impl Rule {
+    fn into_matcher<T>(self, index: usize, word: String) -> Result<Matcher<T>, RuleSetError>
+    where
+        T: 'static + Divisible + Display + Clone + TryInto<i128> + Send + Sync,
+    {
+        let matcher = match &self {
+            Rule::Divisor(0) => return Err(RuleSetError::ZeroDivisor(index)),
+            Rule::Divisor(divisor) if T::from_divisor(*divisor).is_none() => {
+                return Err(RuleSetError::DivisorTooLarge(index))
+            }
+            Rule::Divisor(divisor) => Matcher::divisible_by(*divisor, word),
+            Rule::Digit(digit) if *digit > 9 => return Err(RuleSetError::NotADigit(index)),
+            Rule::Digit(digit) => predicates::contains_digit(*digit, word),
+            Rule::Range { start, end } => {
+                let range = i128::from(*start)..i128::from(*end);
+                Matcher::new_ref(
+                    move |n: &T| n.clone().try_into().is_ok_and(|n| range.contains(&n)),
+                    word,
+                )
+            }
+            Rule::Expression(source) => Expr::parse(source)
+                .map_err(|err| RuleSetError::Expression(index, err))?
+                .into_matcher(word),
+        };
+        Ok(Matcher {
+            rule: Some(self),
+            ..matcher
+        })
+    }
+}
+
+impl RuleSet {
+    /// a Fizzy with a matcher for every rule
+    pub fn to_fizzy<T>(&self) -> Result<Fizzy<T>, RuleSetError>
+    where
+        T: 'static + Divisible + Display + Clone + TryInto<i128> + Send + Sync,
+    {
+        let mut fizzy = Fizzy::new().with_strategy(self.strategy);
+        for (index, rule) in self.rules.iter().enumerate() {
+            let matcher = rule
+                .rule
+                .clone()
+                .into_matcher(index, rule.word.clone())?
+                .with_priority(rule.priority);
+            let matcher = rule
+                .suppresses
+                .iter()
+                .fold(matcher, |matcher, word| matcher.suppresses(word.clone()));
+            fizzy = fizzy.add_matcher(matcher);
+        }
+        Ok(fizzy)
+    }
+
+    /// the rule set of `fizzy`, if all of its matchers were made from rules
+    pub fn from_fizzy<T>(fizzy: &Fizzy<T>) -> Result<RuleSet, RuleSetError> {
+        let rules = fizzy
+            .matchers
+            .iter()
+            .enumerate()
+            .map(|(index, matcher)| match &matcher.rule {
+                Some(rule) => Ok(WordRule {
+                    rule: rule.clone(),
+                    word: matcher.substitute.clone(),
+                    priority: matcher.priority,
+                    suppresses: matcher.suppresses.clone(),
+                }),
+                None => Err(RuleSetError::NotRepresentable(index)),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(RuleSet {
+            rules,
+            strategy: fizzy.strategy,
+        })
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{Fizzy, History};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// Why the stats of a range cannot be collected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatsError {
+    /// this value of the range is not representable in the element type
+    OutOfRange(u64),
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StatsError::OutOfRange(value) => {
+                write!(f, "{} cannot be represented in the element type", value)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StatsError {}
+
+/// One distinct output and how many values in a range produce it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputCount {
+    /// the joined words, or None where the number itself is printed
+    pub output: Option<String>,
+    pub count: u64,
+}
+
+/// A run of consecutive values which are all printed as numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Run {
+    pub start: u64,
+    pub length: u64,
+}
+
+/// What a Fizzy outputs over a range of values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stats {
+    /// how many values are in the range
+    pub total: u64,
+    /// every distinct output, in order of first appearance; the words are
+    /// joined, cased and translated, but not templated
+    pub outputs: Vec<OutputCount>,
+    /// for every matcher, in the order they were added, how many values it
+    /// contributed a word to
+    pub hits: Vec<u64>,
+    /// the first of the longest runs of plain numbers, if there is one
+    pub longest_run: Option<Run>,
+}
+
+/// Stats being collected value by value.
+struct Tally {
+    stats: Stats,
+    /// where each output is in `stats.outputs`
+    positions: HashMap<Option<String>, usize>,
+    run: Option<Run>,
+}
+
+impl Tally {
+    fn new(matchers: usize) -> Self {
+        Tally {
+            stats: Stats {
+                total: 0,
+                outputs: Vec::new(),
+                hits: vec![0; matchers],
+                longest_run: None,
+            },
+            positions: HashMap::new(),
+            run: None,
+        }
+    }
+
+    fn count(&mut self, output: Option<String>, count: u64) {
+        let outputs = &mut self.stats.outputs;
+        let position = *self.positions.entry(output.clone()).or_insert_with(|| {
+            outputs.push(OutputCount { output, count: 0 });
+            outputs.len() - 1
+        });
+        outputs[position].count += count;
+    }
+
+    /// follow the runs of numbers past `value`
+    fn step(&mut self, value: u64, is_number: bool) {
+        self.run = match (self.run, is_number) {
+            (_, false) => None,
+            (None, true) => Some(Run {
+                start: value,
+                length: 1,
+            }),
+            (Some(run), true) => Some(Run {
+                length: run.length + 1,
+                ..run
+            }),
+        };
+        if let Some(run) = self.run {
+            if self
+                .stats
+                .longest_run
+                .is_none_or(|longest| run.length > longest.length)
+            {
+                self.stats.longest_run = Some(run);
+            }
+        }
+    }
+}
+
+/// how many values in `[0, bound]` are `residue` modulo `period`
+fn count_upto(bound: u64, residue: u64, period: u64) -> u64 {
+    match bound.checked_sub(residue) {
+        Some(above) => above / period + 1,
+        None => 0,
+    }
+}
+
+impl<T> Fizzy<T>
+where
+    T: TryFrom<u64>,
+{
+    /// count the outputs for every value in `range`, how often each matcher
+    /// fired and the longest run of plain numbers.
+    ///
+    /// When every matcher is a divisibility rule and the range is at least
+    /// as long as the period of the outputs, the counts are worked out from
+    /// one period, so that even a huge range takes time in proportion to the
+    /// period. Otherwise every value is evaluated like `apply` does.
+    ///
+    /// Either way, a range reaching values which are not representable in T
+    /// is an error.
+    pub fn stats(&self, range: RangeInclusive<u64>) -> Result<Stats, StatsError> {
+        let (start, end) = (*range.start(), *range.end());
+        let mut tally = Tally::new(self.matchers.len());
+        if start > end {
+            return Ok(tally.stats);
+        }
+        for &value in [start, end].iter() {
+            T::try_from(value).map_err(|_| StatsError::OutOfRange(value))?;
+        }
+        tally.stats.total = (end - start).saturating_add(1);
+        match self.unbounded_period() {
+            Ok(period) if period <= tally.stats.total => {
+                self.periodic_stats(start, end, period, &mut tally)
+            }
+            _ => self.scanned_stats(start, end, &mut tally)?,
+        }
+        Ok(tally.stats)
+    }
+
+    fn scanned_stats(&self, start: u64, end: u64, tally: &mut Tally) -> Result<(), StatsError> {
+        let (mut fired, mut history) = (Vec::new(), History::default());
+        for value in start..=end {
+            let element = T::try_from(value).map_err(|_| StatsError::OutOfRange(value))?;
+            self.collect_fired(&element, &history, &mut fired);
+            self.record(&mut history, &fired);
+            for &index in fired.iter() {
+                tally.stats.hits[index] += 1;
+            }
+            tally.count(self.join_fired(&fired), 1);
+            tally.step(value, fired.is_empty());
+        }
+        Ok(())
+    }
+
+    fn periodic_stats(&self, start: u64, end: u64, period: u64, tally: &mut Tally) {
+        // the residues in order of their first value in the range
+        let first = start % period;
+        let mut fired = Vec::new();
+        let mut all_numbers = true;
+        for residue in (first..period).chain(0..first) {
+            self.collect_fired_by(&mut fired, |matcher| matcher.condition.holds(residue));
+            let count = count_upto(end, residue, period)
+                - start
+                    .checked_sub(1)
+                    .map_or(0, |before| count_upto(before, residue, period));
+            if count == 0 {
+                continue;
+            }
+            for &index in fired.iter() {
+                tally.stats.hits[index] += count;
+            }
+            tally.count(self.join_fired(&fired), count);
+            all_numbers &= fired.is_empty();
+        }
+
+        if all_numbers {
+            tally.stats.longest_run = Some(Run {
+                start,
+                length: tally.stats.total,
+            });
+            return;
+        }
+        // every distinct run starts within two periods of `start`, and ends
+        // at most a period later
+        let window = start.saturating_add(period.saturating_mul(2));
+        for value in start..=end {
+            let residue = value % period;
+            self.collect_fired_by(&mut fired, |matcher| matcher.condition.holds(residue));
+            let number = fired.is_empty();
+            if value > window && !number {
+                break;
+            }
+            tally.step(value, number);
+        }
+    }
+}
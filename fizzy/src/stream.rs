@@ -0,0 +1,18 @@
+use crate::{Fizzy, History};
+use futures::stream::{Stream, StreamExt};
+use std::fmt::Display;
+
+impl<T> Fizzy<T>
+where
+    T: Display,
+{
+    /// map this fizzy onto every element of an asynchronous stream, like
+    /// `apply`, yielding the outputs as they are ready
+    pub fn apply_stream<'a, S>(&'a self, stream: S) -> impl Stream<Item = String> + 'a
+    where
+        S: Stream<Item = T> + 'a,
+    {
+        let (mut fired, mut history) = (Vec::new(), History::default());
+        stream.map(move |n| self.evaluate_next(&n, &mut history, &mut fired))
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs;
+use std::process::{Command, Output};
+
+fn fizzy(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_fizzy"))
+        .args(args)
+        .output()
+        .expect("could not run the fizzy binary")
+}
+
+fn stdout(args: &[&str]) -> String {
+    let output = fizzy(args);
+    assert!(output.status.success(), "fizzy {:?} failed", args);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_default_rules_one_per_line() {
+    assert_eq!(
+        "1\n2\nfizz\n4\nbuzz\nfizz\n7\n8\nfizz\nbuzz\n11\nfizz\n13\n14\nfizzbuzz\n",
+        stdout(&["15"])
+    );
+}
+
+#[test]
+fn test_inline_rules_with_separator() {
+    assert_eq!(
+        "bam,8,9,10,11,12,13,bam\n",
+        stdout(&["--rule", "7=bam", "--separator", ",", "7..=14"])
+    );
+}
+
+#[test]
+fn test_exclusive_range() {
+    assert_eq!("fizz 4 buzz\n", stdout(&["--separator", " ", "3..6"]));
+}
+
+#[test]
+fn test_json_output() {
+    assert_eq!(
+        "[\"1\",\"Fizz\",\"3\",\"Fizz\"]\n",
+        stdout(&["--rule", "2=Fizz", "--json", "4"])
+    );
+}
+
+#[test]
+fn test_rules_file() {
+    let file_name = std::env::temp_dir().join("fizzy_test_rules_file.txt");
+    fs::write(&file_name, "# custom rules\n2=zig\n\n3=zag\n").unwrap();
+    let got = stdout(&[
+        "--rules",
+        file_name.to_str().unwrap(),
+        "--separator",
+        " ",
+        "6",
+    ]);
+    fs::remove_file(&file_name).unwrap();
+    assert_eq!("1 zig zag zig 5 zigzag\n", got);
+}
+
+#[test]
+fn test_missing_rules_file_is_named() {
+    let output = fizzy(&["--rules", "/nonexistent/fizzy_rules.txt", "5"]);
+    assert_eq!(Some(1), output.status.code());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("fizzy: /nonexistent/fizzy_rules.txt: "));
+}
+
+#[test]
+fn test_empty_range_prints_nothing() {
+    assert_eq!("", stdout(&["0"]));
+    assert_eq!("", stdout(&["--separator", ",", "0"]));
+}
+
+#[test]
+fn test_invalid_rule_is_a_usage_error() {
+    let output = fizzy(&["--rule", "0=never", "10"]);
+    assert_eq!(Some(2), output.status.code());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn test_missing_range_is_a_usage_error() {
+    assert_eq!(Some(2), fizzy(&["--json"]).status.code());
+}
@@ -118,6 +118,22 @@ fn test_minimal_generic_bounds() {
         }
     }
 
+    impl<'a> Rem for &'a Fizzable {
+        type Output = Fizzable;
+        fn rem(self, rhs: &'a Fizzable) -> Fizzable {
+            *self % *rhs
+        }
+    }
+
+    impl FromPrimitive for Fizzable {
+        fn from_i64(n: i64) -> Option<Fizzable> {
+            u8::from_i64(n).map(Fizzable)
+        }
+        fn from_u64(n: u64) -> Option<Fizzable> {
+            u8::from_u64(n).map(Fizzable)
+        }
+    }
+
     let got = fizz_buzz::<Fizzable>()
         .apply(std::iter::successors(Some(Fizzable(1)), |prev| {
             Some(*prev + 1.into())
@@ -126,3 +142,1442 @@ fn test_minimal_generic_bounds() {
         .collect::<Vec<_>>();
     assert_eq!(expect!(), got);
 }
+
+#[test]
+fn test_combinators() {
+    let expect = vec![
+        "1",
+        "even-or-7",
+        "3",
+        "even-or-7",
+        "5",
+        "six",
+        "even-or-7",
+        "even-or-7",
+    ];
+    let fizzer: Fizzy<i32> = Fizzy::new()
+        .add_matcher(
+            Matcher::new(|n: i32| n % 2 == 0, "six").and(Matcher::new(|n: i32| n % 3 == 0, "")),
+        )
+        .add_matcher(
+            Matcher::new(|n: i32| n % 2 == 0, "even-or-7")
+                .or(Matcher::new(|n: i32| n == 7, ""))
+                .and(Matcher::new(|n: i32| n % 3 == 0, "").not()),
+        );
+    let got = fizzer.apply(1..=8).collect::<Vec<_>>();
+    assert_eq!(expect, got);
+}
+
+#[test]
+fn test_priority_orders_substitutes() {
+    let fizzer: Fizzy<i32> = Fizzy::new()
+        .add_matcher(Matcher::new(|n: i32| n % 3 == 0, "fizz"))
+        .add_matcher(Matcher::new(|n: i32| n % 5 == 0, "buzz").with_priority(1));
+    let got = fizzer.apply(14..=15).collect::<Vec<_>>();
+    assert_eq!(vec!["14", "buzzfizz"], got);
+}
+
+#[test]
+fn test_first_match_strategy() {
+    let fizzer: Fizzy<i32> = fizz_buzz()
+        .add_matcher(Matcher::new(|n: i32| n % 15 == 0, "fizzbuzz!").with_priority(1))
+        .with_strategy(Strategy::FirstMatch);
+    let got = fizzer.apply(1..=16).collect::<Vec<_>>();
+    let mut expect = expect!();
+    expect[14] = "fizzbuzz!";
+    assert_eq!(expect, got);
+}
+
+#[test]
+fn test_suppress_strategy() {
+    let fizzer: Fizzy<i32> = Fizzy::new()
+        .add_matcher(Matcher::new(|n: i32| n % 3 == 0, "fizz").suppresses("buzz"))
+        .add_matcher(Matcher::new(|n: i32| n % 5 == 0, "buzz"))
+        .add_matcher(Matcher::new(|n: i32| n % 2 == 0, "bam"))
+        .with_strategy(Strategy::Suppress);
+    let got = fizzer.apply(5..=10).collect::<Vec<_>>();
+    assert_eq!(vec!["buzz", "fizzbam", "7", "bam", "fizz", "buzzbam"], got);
+}
+
+#[test]
+fn test_suppression_ignored_by_default() {
+    let fizzer: Fizzy<i32> = Fizzy::new()
+        .add_matcher(Matcher::new(|n: i32| n % 3 == 0, "fizz").suppresses("buzz"))
+        .add_matcher(Matcher::new(|n: i32| n % 5 == 0, "buzz"));
+    let got = fizzer.apply(15..=15).collect::<Vec<_>>();
+    assert_eq!(vec!["fizzbuzz"], got);
+}
+
+#[test]
+fn test_apply_borrows() {
+    let fizzer = fizz_buzz::<u32>();
+    let first = fizzer.apply(1..=16).collect::<Vec<_>>();
+    let second = fizzer.apply(1..=16).collect::<Vec<_>>();
+    assert_eq!(expect!(), first);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_clone_is_independent() {
+    let fizzer = fizz_buzz::<i32>();
+    let extended = fizzer
+        .clone()
+        .add_matcher(Matcher::new(|n: i32| n % 7 == 0, "bam"));
+    assert_eq!(vec!["bam"], extended.apply(7..=7).collect::<Vec<_>>());
+    assert_eq!(vec!["7"], fizzer.apply(7..=7).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_shared_between_threads() {
+    fn assert_send_sync<S: Send + Sync>() {}
+    assert_send_sync::<Fizzy<u64>>();
+    assert_send_sync::<Matcher<u64>>();
+
+    let fizzer = fizz_buzz::<u64>();
+    let outputs = std::thread::scope(|scope| {
+        let handles = (0..4_u64)
+            .map(|chunk| {
+                let fizzer = &fizzer;
+                scope.spawn(move || {
+                    let start = chunk * 4 + 1;
+                    fizzer.apply(start..start + 4).collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(expect!(), outputs);
+}
+
+#[test]
+fn test_apply_to_fmt_writer() {
+    let mut got = String::new();
+    fizz_buzz::<i32>()
+        .apply_to_fmt_writer(1..=16, &mut got, ",")
+        .unwrap();
+    assert_eq!(expect!().join(","), got);
+}
+
+#[test]
+fn test_apply_to_writer_matches_apply() {
+    let fizzer: Fizzy<i32> = fizz_buzz()
+        .add_matcher(Matcher::new(|n: i32| n % 7 == 0, "bam").suppresses("fizz"))
+        .with_strategy(Strategy::Suppress);
+    let mut got = Vec::new();
+    fizzer.apply_to_writer(1..=105, &mut got, "\n").unwrap();
+    let expect = fizzer.apply(1..=105).collect::<Vec<_>>().join("\n");
+    assert_eq!(expect, String::from_utf8(got).unwrap());
+}
+
+#[test]
+fn test_apply_to_writer_reports_io_errors() {
+    struct Full;
+    impl std::io::Write for Full {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::WriteZero, "full"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let err = fizz_buzz::<u8>()
+        .apply_to_writer(1..=3, &mut Full, " ")
+        .unwrap_err();
+    assert_eq!(std::io::ErrorKind::WriteZero, err.kind());
+}
+
+#[test]
+fn test_compiled_divisibility_rules_use_a_table() {
+    let compiled = fizz_buzz::<u64>().compile();
+    assert_eq!(Some(15), compiled.period());
+    assert_eq!(expect!(), compiled.apply(1..=16).collect::<Vec<_>>());
+    assert_eq!(Some("buzz"), compiled.nth(1_000_000_000_000).as_deref());
+    assert_eq!(
+        Some("1000000000001"),
+        compiled.nth(1_000_000_000_001).as_deref()
+    );
+    assert_eq!(Some("fizzbuzz"), compiled.nth(1_000_000_000_005).as_deref());
+}
+
+#[test]
+fn test_compiled_nth_outside_the_element_type() {
+    let compiled = fizz_buzz::<u8>().with_template("{words}!").compile();
+    assert_eq!(Some("fizzbuzz!"), compiled.nth(15).as_deref());
+    assert_eq!(Some("fizzbuzz!"), compiled.nth(255).as_deref());
+    assert_eq!(None, compiled.nth(300));
+    let opaque = Fizzy::<u8>::new()
+        .add_matcher(Matcher::new(|n: u8| n > 100, "big"))
+        .compile();
+    assert_eq!(None, opaque.period());
+    assert_eq!(None, opaque.nth(300));
+}
+
+#[test]
+fn test_compiled_matches_apply() {
+    let fizzer: Fizzy<i64> = fizz_buzz()
+        .add_matcher(
+            Matcher::divisible_by(7, "bam")
+                .and(Matcher::divisible_by(2, "").not())
+                .with_priority(2)
+                .suppresses("buzz"),
+        )
+        .add_matcher(Matcher::divisible_by(4, "quad").or(Matcher::divisible_by(9, "")))
+        .with_strategy(Strategy::Suppress);
+    let compiled = fizzer.compile();
+    assert_eq!(Some(1260), compiled.period());
+    assert_eq!(
+        fizzer.apply(-300..3000).collect::<Vec<_>>(),
+        compiled.apply(-300..3000).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_compiled_period_larger_than_element_type() {
+    let fizzer: Fizzy<u8> = fizz_buzz().add_matcher(Matcher::divisible_by(19, "bam"));
+    let compiled = fizzer.compile();
+    assert_eq!(Some(285), compiled.period());
+    assert_eq!(
+        fizzer.apply(0..=255).collect::<Vec<_>>(),
+        compiled.apply(0..=255).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_compiled_falls_back_for_arbitrary_predicates() {
+    let fizzer: Fizzy<i32> = fizz_buzz().add_matcher(Matcher::new(|n: i32| n > 10, "big"));
+    let compiled = fizzer.compile();
+    assert_eq!(None, compiled.period());
+    assert_eq!(Some("fizzbig"), compiled.nth(12).as_deref());
+    assert_eq!(
+        fizzer.apply(1..=30).collect::<Vec<_>>(),
+        compiled.apply(1..=30).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_evaluate_single_value() {
+    let fizzer = fizz_buzz::<u64>();
+    assert_eq!("fizzbuzz", fizzer.evaluate(1_000_000_000_005));
+    assert_eq!("1000000000001", fizzer.evaluate(1_000_000_000_001));
+    assert_eq!("fizz", fizzer.compile().evaluate(1_000_000_000_002));
+}
+
+#[test]
+fn test_range_is_half_open() {
+    let fizzer = fizz_buzz::<i32>();
+    assert_eq!(
+        vec!["buzz", "fizz", "7"],
+        fizzer.range(5, 8).collect::<Vec<_>>()
+    );
+    assert!(fizzer.range(8, 8).next().is_none());
+}
+
+#[test]
+fn test_range_at_a_large_offset() {
+    let start = 1_000_000_000_000_u64;
+    let expect = vec![
+        "buzz",
+        "1000000000001",
+        "fizz",
+        "1000000000003",
+        "1000000000004",
+        "fizzbuzz",
+    ];
+    assert_eq!(
+        expect,
+        fizz_buzz::<u64>()
+            .range(start, start + 6)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        expect,
+        fizz_buzz::<u64>()
+            .compile()
+            .range(start, start + 6)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_range_on_floats() {
+    let got = fizz_buzz::<f64>().range(1.0, 17.0).collect::<Vec<_>>();
+    assert_eq!(expect!(), got);
+}
+
+#[test]
+fn test_evaluate_detailed() {
+    let fizzer = fizz_buzz::<i32>();
+    assert_eq!(Output::Value(7), fizzer.evaluate_detailed(7));
+    assert_eq!(
+        Output::Words(vec![
+            MatchedRule {
+                index: 0,
+                substitute: String::from("fizz")
+            },
+            MatchedRule {
+                index: 1,
+                substitute: String::from("buzz")
+            },
+        ]),
+        fizzer.evaluate_detailed(30)
+    );
+}
+
+#[test]
+fn test_detailed_tells_words_from_numbers() {
+    let fizzer: Fizzy<i32> = Fizzy::new().add_matcher(Matcher::new(|n: i32| n == 2, "3"));
+    let got = fizzer.apply_detailed(2..=3).collect::<Vec<_>>();
+    assert!(matches!(got[0], Output::Words(_)));
+    assert_eq!(Output::Value(3), got[1]);
+    assert_eq!(
+        fizzer.apply(2..=3).collect::<Vec<_>>(),
+        got.iter()
+            .map(|output| output.to_string())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_detailed_indices_follow_insertion_order() {
+    let fizzer: Fizzy<u32> = fizz_buzz()
+        .add_matcher(Matcher::divisible_by(15, "fizzbuzz!").with_priority(1))
+        .with_strategy(Strategy::FirstMatch);
+    let mut hits = vec![0; 3];
+    for output in fizzer.apply_detailed(1..=100) {
+        if let Output::Words(rules) = output {
+            rules.iter().for_each(|rule| hits[rule.index] += 1);
+        }
+    }
+    assert_eq!(vec![27, 14, 6], hits);
+}
+
+#[test]
+fn test_non_copy_elements() {
+    let fizzer: Fizzy<String> = Fizzy::new()
+        .add_matcher(Matcher::new_ref(|s: &String| s.starts_with('a'), "first"))
+        .add_matcher(Matcher::new(|s: String| s.len() > 5, "long"));
+    let words = vec!["apple", "banana", "avocado", "fig"];
+    let got = fizzer
+        .apply(words.into_iter().map(String::from))
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["first", "long", "firstlong", "fig"], got);
+}
+
+#[test]
+fn test_divisibility_checks_do_not_clone() {
+    use std::fmt;
+    use std::ops::Rem;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static CLONES: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, PartialEq)]
+    struct Counted(u64);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Counted {
+            CLONES.fetch_add(1, Ordering::SeqCst);
+            Counted(self.0)
+        }
+    }
+
+    impl From<u8> for Counted {
+        fn from(n: u8) -> Counted {
+            Counted(n.into())
+        }
+    }
+
+    impl fmt::Display for Counted {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl<'a> Rem for &'a Counted {
+        type Output = Counted;
+        fn rem(self, rhs: &'a Counted) -> Counted {
+            Counted(self.0 % rhs.0)
+        }
+    }
+
+    impl FromPrimitive for Counted {
+        fn from_i64(n: i64) -> Option<Counted> {
+            u64::from_i64(n).map(Counted)
+        }
+        fn from_u64(n: u64) -> Option<Counted> {
+            Some(Counted(n))
+        }
+    }
+
+    let fizzer = fizz_buzz::<Counted>();
+    let clones = CLONES.load(Ordering::SeqCst);
+    let got = fizzer.apply((1..=16).map(Counted)).collect::<Vec<_>>();
+    assert_eq!(expect!(), got);
+    assert_eq!(clones, CLONES.load(Ordering::SeqCst));
+}
+
+#[test]
+fn test_largest_divisor_of_the_element_type() {
+    let fizzer = Fizzy::<u8>::new().add_matcher(Matcher::divisible_by(255, "x"));
+    assert_eq!(
+        vec!["x", "254", "x"],
+        fizzer
+            .apply([0, 254, 255].iter().cloned())
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+#[should_panic(expected = "300 cannot be represented in the element type")]
+fn test_divisor_too_large_for_the_element_type() {
+    Fizzy::<u8>::new().add_matcher(Matcher::divisible_by(300, "x"));
+}
+
+#[test]
+#[cfg(feature = "bigint")]
+fn test_big_integers() {
+    let start = BigUint::from(u128::MAX);
+    let end = start.clone() + BigUint::from(16_u8);
+    // u128::MAX is divisible by both 3 and 5
+    let expect = vec![
+        "fizzbuzz",
+        "340282366920938463463374607431768211456",
+        "340282366920938463463374607431768211457",
+        "fizz",
+        "340282366920938463463374607431768211459",
+        "buzz",
+        "fizz",
+    ];
+    let got = fizz_buzz::<BigUint>()
+        .range(start.clone(), end.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(expect, got[..7]);
+    assert_eq!(
+        got,
+        fizz_buzz::<BigUint>()
+            .compile()
+            .range(start, end)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_predicate_contains_digit() {
+    let fizzer: Fizzy<u32> = Fizzy::new()
+        .add_matcher(predicates::divisible_by(3, "fizz"))
+        .add_matcher(predicates::contains_digit(3, "fizz"));
+    let got = fizzer.apply(12..=14).collect::<Vec<_>>();
+    assert_eq!(vec!["fizz", "fizz", "14"], got);
+}
+
+#[test]
+fn test_predicate_digit_sum() {
+    let fizzer: Fizzy<i64> =
+        Fizzy::new().add_matcher(predicates::digit_sum_divisible_by(5, "five"));
+    let got = fizzer
+        .apply(vec![14, -23, 18, 5, 55].into_iter())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["five", "five", "18", "five", "five"], got);
+}
+
+#[test]
+fn test_predicate_primes() {
+    let fizzer: Fizzy<i64> = Fizzy::new().add_matcher(predicates::is_prime("prime"));
+    let primes = fizzer
+        .apply(-5..30)
+        .filter(|output| output == "prime")
+        .count();
+    assert_eq!(10, primes);
+    let big: Fizzy<u64> = Fizzy::new().add_matcher(predicates::is_prime("prime"));
+    assert_eq!("prime", big.evaluate(18_446_744_073_709_551_557));
+    assert_eq!("3215031751", big.evaluate(3_215_031_751));
+}
+
+#[test]
+fn test_predicate_perfect_square_and_palindrome() {
+    let fizzer: Fizzy<u64> = Fizzy::new()
+        .add_matcher(predicates::is_perfect_square("square"))
+        .add_matcher(predicates::is_palindrome("pal"));
+    let got = fizzer
+        .apply(vec![0, 10, 16, 121, 123, 4_294_967_296].into_iter())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec!["squarepal", "10", "square", "squarepal", "123", "square"],
+        got
+    );
+}
+
+#[test]
+fn test_predicate_in_range() {
+    let fizzer: Fizzy<i32> = fizz_buzz()
+        .add_matcher(predicates::in_range(13..20, "teen").with_priority(1))
+        .with_strategy(Strategy::FirstMatch);
+    let got = fizzer.apply(11..=21).collect::<Vec<_>>();
+    assert_eq!(
+        vec!["11", "fizz", "teen", "teen", "teen", "teen", "teen", "teen", "teen", "buzz", "fizz"],
+        got
+    );
+}
+
+#[test]
+fn test_expression_rule() {
+    let fizzer: Fizzy<u32> =
+        fizz_buzz().add_matcher(expr::parse_rule(r#"n % 7 == 0 && n > 100 => "bang""#).unwrap());
+    let got = fizzer
+        .apply(vec![7, 98, 105, 112].into_iter())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["7", "98", "fizzbuzzbang", "bang"], got);
+}
+
+#[test]
+fn test_expression_precedence_and_parentheses() {
+    let expr = expr::Expr::parse("!(n - 2 * 3 < 0) || n == -1 && true").unwrap();
+    assert!(!expr.matches(5));
+    assert!(expr.matches(6));
+    assert!(expr.matches(-1));
+    let expr: expr::Expr = "((n + 1) % 4 == 0) == (n > 10)".parse().unwrap();
+    assert!(expr.matches(11));
+    assert!(!expr.matches(3));
+    assert!(expr.matches(4));
+}
+
+#[test]
+fn test_expression_arithmetic_errors_do_not_match() {
+    let fizzer: Fizzy<i64> =
+        Fizzy::new().add_matcher(expr::parse_rule(r#"10 / (n - 3) == 5 => "five""#).unwrap());
+    let got = fizzer.apply(vec![3, 5].into_iter()).collect::<Vec<_>>();
+    assert_eq!(vec!["3", "five"], got);
+}
+
+#[test]
+fn test_expression_parse_errors_have_columns() {
+    use expr::{Expr, ParseErrorKind};
+    let column = |source: &str| Expr::parse(source).unwrap_err().column;
+    assert_eq!(14, column("n % 7 == 0 &&"));
+    assert_eq!(7, column("n % 7 = 0"));
+    assert_eq!(7, column("(n > 3"));
+    assert_eq!(
+        ParseErrorKind::UnknownName(String::from("m")),
+        Expr::parse("m > 3").unwrap_err().kind
+    );
+    assert_eq!(
+        ParseErrorKind::TypeMismatch {
+            expected: "a condition"
+        },
+        Expr::parse("n + 1").unwrap_err().kind
+    );
+    let err = Expr::parse("n > 1 && n + 2").unwrap_err();
+    assert_eq!(10, err.column);
+    assert_eq!("column 10: expected a condition", err.to_string());
+}
+
+#[test]
+fn test_expression_rule_errors() {
+    let err = expr::parse_rule::<u32>("n > 3 \"big\"").unwrap_err();
+    assert_eq!(7, err.column);
+    assert_eq!("column 7: expected '=>', found a string", err.to_string());
+    let err = expr::parse_rule::<u32>("n > 3 => big").unwrap_err();
+    assert_eq!(10, err.column);
+    let err = expr::parse_rule::<u32>("n > 3 => \"big").unwrap_err();
+    assert_eq!(10, err.column);
+}
+
+#[test]
+fn test_expression_nesting_is_limited() {
+    use expr::{Expr, ParseErrorKind};
+    let nested = |open: &str, depth: usize, inner: &str, close: &str| {
+        format!("{}{}{}", open.repeat(depth), inner, close.repeat(depth))
+    };
+    assert!(Expr::parse(&nested("(", 100, "n == 1", ")")).is_ok());
+    assert!(Expr::parse(&nested("!", 100, "true", "")).is_ok());
+
+    let err = Expr::parse(&nested("(", 5_000, "n == 1", ")")).unwrap_err();
+    assert_eq!(ParseErrorKind::TooDeep, err.kind);
+    assert_eq!(101, err.column);
+    assert_eq!("column 101: expression nested too deeply", err.to_string());
+    let err = Expr::parse(&nested("-", 5_000, "n == 1", "")).unwrap_err();
+    assert_eq!((101, ParseErrorKind::TooDeep), (err.column, err.kind));
+    let chain = format!("n{} == 0", " + 1".repeat(5_000));
+    assert_eq!(
+        ParseErrorKind::TooDeep,
+        Expr::parse(&chain).unwrap_err().kind
+    );
+    assert!(expr::parse_rule::<u32>(&nested("!", 5_000, "true => \"x\"", "")).is_err());
+}
+
+#[test]
+fn test_expression_comparisons_do_not_chain() {
+    let err = expr::Expr::parse("1 < n < 10").unwrap_err();
+    assert_eq!(7, err.column);
+}
+
+#[test]
+fn test_analyze_fizz_buzz() {
+    let analysis = fizz_buzz::<u32>().analyze().unwrap();
+    assert_eq!(15, analysis.period);
+    assert!(analysis.unreachable.is_empty());
+    assert!(analysis.redundant.is_empty());
+    let counts = analysis
+        .outputs
+        .iter()
+        .map(|density| (density.output.as_deref(), density.count))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (Some("fizzbuzz"), 1),
+            (None, 8),
+            (Some("fizz"), 4),
+            (Some("buzz"), 2)
+        ],
+        counts
+    );
+    assert!((analysis.outputs[1].density - 8.0 / 15.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_analyze_redundant_and_unreachable() {
+    let fizzer: Fizzy<u32> = fizz_buzz()
+        .add_matcher(Matcher::divisible_by(6, "six"))
+        .add_matcher(Matcher::divisible_by(2, "never").and(Matcher::divisible_by(2, "").not()))
+        .add_matcher(
+            Matcher::divisible_by(10, "ten")
+                .with_priority(-1)
+                .suppresses("never"),
+        );
+    let analysis = fizzer.analyze().unwrap();
+    assert_eq!(30, analysis.period);
+    assert_eq!(vec![3], analysis.unreachable);
+    assert_eq!(
+        vec![
+            Implication {
+                matcher: 2,
+                implies: 0
+            },
+            Implication {
+                matcher: 4,
+                implies: 1
+            },
+        ],
+        analysis.redundant
+    );
+}
+
+#[test]
+fn test_analyze_shadowed_by_strategy() {
+    let fizzer: Fizzy<u32> = fizz_buzz()
+        .add_matcher(Matcher::divisible_by(15, "fizzbuzz!"))
+        .with_strategy(Strategy::FirstMatch);
+    assert_eq!(vec![2], fizzer.analyze().unwrap().unreachable);
+}
+
+#[test]
+fn test_analyze_needs_divisibility_rules() {
+    let fizzer: Fizzy<u32> = fizz_buzz().add_matcher(predicates::is_prime("prime"));
+    assert_eq!(Err(AnalysisError::NotDivisibility(2)), fizzer.analyze());
+    let fizzer: Fizzy<u64> = [65_521, 65_519]
+        .iter()
+        .fold(Fizzy::new(), |fizzer, &divisor| {
+            fizzer.add_matcher(Matcher::divisible_by(divisor, "big"))
+        });
+    assert_eq!(Err(AnalysisError::PeriodTooLarge), fizzer.analyze());
+}
+
+#[test]
+fn test_fallback_roman() {
+    let fizzer = fizz_buzz::<u32>().with_fallback(fallback::roman());
+    let got = fizzer.apply(1..=8).collect::<Vec<_>>();
+    assert_eq!(
+        vec!["I", "II", "fizz", "IV", "buzz", "fizz", "VII", "VIII"],
+        got
+    );
+    assert_eq!("MMMCMXCVII", fizzer.evaluate(3997));
+    assert_eq!("4001", fizzer.evaluate(4001));
+}
+
+#[test]
+fn test_fallback_hexadecimal() {
+    let fizzer = fizz_buzz::<i64>().with_fallback(fallback::hexadecimal());
+    let got = fizzer
+        .apply(vec![-26, 11, 254, 255].into_iter())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["-1a", "b", "fe", "fizzbuzz"], got);
+}
+
+#[test]
+fn test_fallback_zero_padded() {
+    let fizzer = fizz_buzz::<i32>().with_fallback(fallback::zero_padded(4));
+    let got = fizzer
+        .apply(vec![7, -7, 12346].into_iter())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["0007", "-007", "12346"], got);
+}
+
+#[test]
+fn test_fallback_thousands() {
+    let fizzer = fizz_buzz::<i64>().with_fallback(fallback::thousands('.'));
+    let got = fizzer
+        .apply(vec![1, 1_000, -1_234_567, 100_000_001].into_iter())
+        .collect::<Vec<_>>();
+    assert_eq!(vec!["1", "buzz", "-1.234.567", "100.000.001"], got);
+    let fizzer = fizz_buzz::<f64>().with_fallback(fallback::thousands(' '));
+    assert_eq!("1 234 567.5", fizzer.evaluate(1_234_567.5));
+}
+
+#[test]
+fn test_fallback_everywhere() {
+    let fizzer = fizz_buzz::<u64>().with_fallback(|n: &u64| format!("<{}>", n));
+    let expect = vec!["<1>", "<2>", "fizz", "<4>", "buzz"];
+    assert_eq!(expect, fizzer.apply(1..=5).collect::<Vec<_>>());
+    assert_eq!(expect, fizzer.compile().apply(1..=5).collect::<Vec<_>>());
+    assert_eq!(
+        Some("<1000000000001>"),
+        fizzer.compile().nth(1_000_000_000_001).as_deref()
+    );
+    let mut written = String::new();
+    fizzer
+        .apply_to_fmt_writer(1..=5, &mut written, " ")
+        .unwrap();
+    assert_eq!(expect.join(" "), written);
+    assert_eq!(Output::Value(4), fizzer.evaluate_detailed(4));
+}
+
+#[test]
+fn test_joiner_and_title_casing() {
+    let fizzer = fizz_buzz::<i32>()
+        .with_joiner("-")
+        .with_casing(Casing::Title);
+    let got = fizzer.apply(13..=16).collect::<Vec<_>>();
+    assert_eq!(vec!["13", "14", "Fizz-Buzz", "16"], got);
+    assert_eq!("Fizz", fizzer.evaluate(3));
+}
+
+#[test]
+fn test_joiner_with_spaces() {
+    let fizzer = fizz_buzz::<i32>().with_joiner(" ");
+    assert_eq!("fizz buzz", fizzer.evaluate(30));
+    assert_eq!("buzz", fizzer.evaluate(10));
+}
+
+#[test]
+fn test_casing() {
+    let fizzer = fizz_buzz::<i32>().with_casing(Casing::Upper);
+    assert_eq!("FIZZBUZZ", fizzer.evaluate(15));
+    let fizzer = fizz_buzz::<i32>()
+        .with_joiner(" ")
+        .with_casing(Casing::CapitaliseFirst);
+    assert_eq!("Fizz buzz", fizzer.evaluate(15));
+    let fizzer = Fizzy::new()
+        .add_matcher(Matcher::divisible_by(2, "EVEN"))
+        .with_casing(Casing::Lower);
+    assert_eq!("even", fizzer.evaluate(4_u32));
+}
+
+#[test]
+fn test_template() {
+    let fizzer = fizz_buzz::<i32>()
+        .with_joiner("+")
+        .with_template("{words} ({n})");
+    let got = fizzer.apply(14..=16).collect::<Vec<_>>();
+    assert_eq!(vec!["14", "fizz+buzz (15)", "16"], got);
+    let fizzer = fizz_buzz::<i32>().with_template("{n}: {x} {words}");
+    assert_eq!("9: {x} fizz", fizzer.evaluate(9));
+}
+
+#[test]
+fn test_template_everywhere() {
+    let fizzer = fizz_buzz::<u64>()
+        .with_joiner("-")
+        .with_casing(Casing::Title)
+        .with_template("[{words}]")
+        .with_fallback(fallback::roman());
+    let expect = vec!["XIV", "[Fizz-Buzz]", "XVI"];
+    assert_eq!(expect, fizzer.apply(14..=16).collect::<Vec<_>>());
+    assert_eq!(expect, fizzer.compile().apply(14..=16).collect::<Vec<_>>());
+    assert_eq!(Some("[Buzz]"), fizzer.compile().nth(20).as_deref());
+    let mut written = String::new();
+    fizzer
+        .apply_to_fmt_writer(14..=16, &mut written, ",")
+        .unwrap();
+    assert_eq!(expect.join(","), written);
+}
+
+#[test]
+fn test_stateful_after_number() {
+    let fizzer = Fizzy::new()
+        .add_matcher(Matcher::divisible_by(3, "fizz").and(Matcher::stateful(
+            |_: &i32, history: &History| history.previous_was_number(),
+            "",
+        )))
+        .add_matcher(Matcher::divisible_by(5, "buzz"));
+    let got = fizzer.apply(1..=10).collect::<Vec<_>>();
+    let expect = vec!["1", "2", "fizz", "4", "buzz", "6", "7", "8", "fizz", "buzz"];
+    assert_eq!(expect, got);
+    let mut written = String::new();
+    fizzer
+        .apply_to_fmt_writer(1..=10, &mut written, ",")
+        .unwrap();
+    assert_eq!(expect.join(","), written);
+    assert_eq!(expect, fizzer.compile().apply(1..=10).collect::<Vec<_>>());
+    assert_eq!(None, fizzer.compile().period());
+}
+
+#[test]
+fn test_stateful_every_fourth_word() {
+    let is_word = Matcher::divisible_by(3, "").or(Matcher::divisible_by(5, ""));
+    let bazz = Matcher::stateful(
+        |_: &u32, history: &History| history.words_so_far() % 4 == 3,
+        "bazz",
+    )
+    .and(is_word);
+    let fizzer = fizz_buzz().add_matcher(bazz.with_priority(1));
+    let got = fizzer.apply(1..=12).collect::<Vec<_>>();
+    assert_eq!(
+        vec!["1", "2", "fizz", "4", "buzz", "fizz", "7", "8", "bazzfizz", "buzz", "11", "fizz"],
+        got
+    );
+}
+
+#[test]
+fn test_history() {
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let log = seen.clone();
+    let fizzer = fizz_buzz::<i32>().add_matcher(Matcher::stateful(
+        move |_, history: &History| {
+            let previous = history.previous().map(|words| words.join("+"));
+            log.lock()
+                .unwrap()
+                .push((history.index(), history.numbers_so_far(), previous));
+            false
+        },
+        "never",
+    ));
+    assert_eq!(
+        5,
+        fizzer.apply(14..=16).count() + fizzer.apply(1..=2).count()
+    );
+    let expect = vec![
+        (0, 0, None),
+        (1, 1, Some(String::from(""))),
+        (2, 1, Some(String::from("fizz+buzz"))),
+        (0, 0, None),
+        (1, 1, Some(String::from(""))),
+    ];
+    assert_eq!(expect, *seen.lock().unwrap());
+    assert_eq!("fizz", fizzer.evaluate(3));
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn test_par_apply() {
+    let fizzer = fizz_buzz::<u32>()
+        .add_matcher(predicates::contains_digit(7, "bang"))
+        .with_joiner("-")
+        .with_fallback(fallback::thousands(','));
+    let got = fizzer.par_apply(0..100_000_u32);
+    assert_eq!(fizzer.apply(0..100_000).collect::<Vec<_>>(), got);
+    assert_eq!(expect!(), fizz_buzz::<i32>().par_apply(1..17));
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn test_par_apply_stateful() {
+    let fizzer = fizz_buzz::<u32>().add_matcher(Matcher::stateful(
+        |_, history: &History| history.previous_was_number() && history.index().is_multiple_of(2),
+        "!",
+    ));
+    let values = (1..1_000).collect::<Vec<_>>();
+    assert_eq!(
+        fizzer.apply(values.clone().into_iter()).collect::<Vec<_>>(),
+        fizzer.par_apply(values)
+    );
+}
+
+#[test]
+fn test_macro() {
+    let fizzer = fizzy! { 3 => "fizz", 5 => "buzz" };
+    let got = fizzer.apply(1..=16).collect::<Vec<_>>();
+    assert_eq!(expect!(), got);
+    assert_eq!(Some(15), fizzer.compile().period());
+}
+
+#[test]
+fn test_macro_rule_kinds() {
+    let big = String::from("big");
+    let fizzer = fizzy! {
+        3 => "fizz",
+        5 => "buzz",
+        contains(7) => "bang",
+        digit_sum(10) => "ten",
+        prime => "prime",
+        palindrome => "pal",
+        square => "sq",
+        in(20..22) => "twenty",
+        |n| n > 100 => big,
+    };
+    let got = fizzer
+        .apply(vec![4_u64, 7, 19, 21, 22, 101].into_iter())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            "palsq",
+            "bangprimepal",
+            "tenprime",
+            "fizztwenty",
+            "pal",
+            "primepalbig"
+        ],
+        got
+    );
+    let empty: Fizzy<i32> = fizzy! {};
+    assert_eq!("4", empty.evaluate(4));
+}
+
+#[test]
+fn test_infer_fizz_buzz() {
+    let sample = fizz_buzz::<u64>().apply(1..=30).collect::<Vec<_>>();
+    let inference = infer(&sample, 1).unwrap();
+    let rules = vec![
+        InferredRule {
+            divisor: 3,
+            word: String::from("fizz"),
+        },
+        InferredRule {
+            divisor: 5,
+            word: String::from("buzz"),
+        },
+    ];
+    assert_eq!(rules, inference.rules);
+    assert_eq!(sample, inference.fizzy.apply(1..=30).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_infer_splits_combined_words() {
+    let fizzer = fizzy! { 2 => "ab", 3 => "a", 7 => "b" };
+    let sample = fizzer.apply(10..=45).collect::<Vec<_>>();
+    let inference = infer(&sample, 10).unwrap();
+    assert_eq!(3, inference.rules.len());
+    assert_eq!(sample, inference.fizzy.apply(10..=45).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_infer_prefers_fewest_rules() {
+    // "buzzfizz" could be a word of its own, but 15 = 3 * 5 explains it
+    let sample = [
+        "1", "2", "fizz", "4", "buzz", "fizz", "7", "8", "fizz", "buzz",
+    ];
+    let inference = infer(&sample, 1).unwrap();
+    assert_eq!(2, inference.rules.len());
+    let sample = fizzy! { 3 => "fizz", 5 => "buzz", 15 => "!" }
+        .apply(1..=20)
+        .collect::<Vec<_>>();
+    let inference = infer(&sample, 1).unwrap();
+    assert_eq!(
+        vec![3, 5, 15],
+        inference
+            .rules
+            .iter()
+            .map(|rule| rule.divisor)
+            .collect::<Vec<_>>()
+    );
+    let sample = ["fizzbuzz", "1", "2", "fizz"];
+    assert_eq!(2, infer(&sample, 0).unwrap().rules.len());
+}
+
+#[test]
+fn test_infer_from_a_large_start() {
+    let start = 1_000_000_000_000;
+    let sample = fizz_buzz::<u64>()
+        .apply(start..=start + 29)
+        .collect::<Vec<_>>();
+    let inference = infer(&sample, start).unwrap();
+    assert_eq!(
+        vec![3, 5],
+        inference
+            .rules
+            .iter()
+            .map(|rule| rule.divisor)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        sample,
+        inference
+            .fizzy
+            .apply(start..=start + 29)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_infer_errors() {
+    let sample = ["1", "2", "fizz", "4", "buzz", "buzz"];
+    let err = infer(&sample, 1).unwrap_err();
+    assert_eq!(
+        InferenceError::NoRuleSet {
+            value: 6,
+            output: String::from("buzz")
+        },
+        err
+    );
+    assert_eq!(
+        "no divisibility rules explain \"buzz\" for 6 together with the outputs before it",
+        err.to_string()
+    );
+    assert_eq!(
+        Err(InferenceError::NoRuleSet {
+            value: 6,
+            output: String::from("6!")
+        }),
+        infer(&["1", "2", "x", "4", "5", "6!", "7"], 1).map(|inference| inference.rules)
+    );
+    assert_eq!(
+        Err(InferenceError::WrongNumber {
+            value: 2,
+            found: String::from("3")
+        }),
+        infer(&["1", "3"], 1).map(|inference| inference.rules)
+    );
+    assert_eq!(
+        Err(InferenceError::EmptyOutput { value: 2 }),
+        infer(&["1", ""], 1).map(|inference| inference.rules)
+    );
+    assert_eq!(
+        Ok(Vec::new()),
+        infer(&["7", "8"], 7).map(|inference| inference.rules)
+    );
+    assert_eq!(
+        Err(InferenceError::NoRuleSet {
+            value: 0,
+            output: String::from("0")
+        }),
+        infer(&["0", "1", "fizz"], 0).map(|inference| inference.rules)
+    );
+}
+
+fn catalog() -> Catalog {
+    Catalog::new()
+        .with_word("de", "fizz", "brum")
+        .with_word("de", "buzz", "summ")
+        .with_word("snake", "fizz", "sss")
+}
+
+#[test]
+fn test_locale_switching() {
+    let fizzer = fizz_buzz::<i32>().with_catalog(catalog()).with_joiner(" ");
+    let german = fizzer.in_locale("de").unwrap();
+    assert_eq!(Some("de"), german.locale());
+    assert_eq!(
+        vec!["brum", "4", "summ", "brum summ"],
+        german
+            .apply(vec![3, 4, 5, 15].into_iter())
+            .collect::<Vec<_>>()
+    );
+    // missing words fall back to the key
+    let snake = fizzer.in_locale("snake").unwrap();
+    assert_eq!("sss buzz", snake.evaluate(15));
+    assert_eq!("fizz buzz", fizzer.evaluate(15));
+    assert_eq!(Some("brum summ"), german.compile().nth(30).as_deref());
+    assert_eq!(
+        Output::Words(vec![MatchedRule {
+            index: 0,
+            substitute: String::from("brum")
+        }]),
+        german.evaluate_detailed(3)
+    );
+}
+
+#[test]
+fn test_locale_translates_added_matchers() {
+    let german = fizz_buzz::<i32>()
+        .with_catalog(catalog().with_word("de", "bang", "knall"))
+        .in_locale("de")
+        .unwrap()
+        .add_matcher(Matcher::divisible_by(7, "bang"));
+    assert_eq!("brumknall", german.evaluate(21));
+}
+
+#[test]
+fn test_locale_errors() {
+    let err = fizz_buzz::<i32>().in_locale("de").map(drop).unwrap_err();
+    assert_eq!("no catalog for the locale de", err.to_string());
+    assert!(fizz_buzz::<i32>()
+        .with_catalog(catalog())
+        .in_locale("fr")
+        .is_err());
+    let err = Catalog::new()
+        .with_entries("fr", "# French\nfizz = pschitt\n\nbuzz\n")
+        .unwrap_err();
+    assert_eq!(
+        "line 4 of the fr catalog: expected `key = word`",
+        err.to_string()
+    );
+}
+
+#[test]
+fn test_catalog_files() {
+    let dir = std::env::temp_dir().join(format!("fizzy-catalogs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("de.catalog"),
+        "# German\nfizz = brum\nbuzz = summ\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("sv.catalog"), "fizz=fiss\n").unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a catalog").unwrap();
+    let catalog = Catalog::load_dir(&dir);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let catalog = catalog.unwrap();
+    assert_eq!(vec!["de", "sv"], catalog.locales().collect::<Vec<_>>());
+    assert_eq!(Some("fiss"), catalog.translate("sv", "fizz"));
+    assert_eq!(None, catalog.translate("sv", "buzz"));
+    let fizzer = fizz_buzz::<u32>().with_catalog(catalog);
+    assert_eq!("fissbuzz", fizzer.in_locale("sv").unwrap().evaluate(15));
+    assert!(matches!(
+        Catalog::new().load_file("de", dir.join("de.catalog")),
+        Err(CatalogError::Io(..))
+    ));
+}
+
+#[test]
+#[cfg(feature = "futures")]
+fn test_apply_stream() {
+    use futures::executor::block_on;
+    use futures::stream::{self, StreamExt};
+
+    let fizzer = fizz_buzz::<i32>();
+    let got = block_on(
+        fizzer
+            .apply_stream(stream::iter(1..=16))
+            .collect::<Vec<_>>(),
+    );
+    assert_eq!(expect!(), got);
+}
+
+#[test]
+#[cfg(feature = "futures")]
+fn test_apply_stream_from_channel() {
+    use futures::channel::mpsc;
+    use futures::executor::LocalPool;
+    use futures::stream::StreamExt;
+    use futures::task::LocalSpawnExt;
+
+    let fizzer = fizz_buzz::<u64>().add_matcher(Matcher::stateful(
+        |_, history: &History| history.previous_was_number() && history.index() > 10,
+        "!",
+    ));
+    let (sender, receiver) = mpsc::unbounded();
+    let mut pool = LocalPool::new();
+    pool.spawner()
+        .spawn_local(async move {
+            for n in 1..=16 {
+                sender.unbounded_send(n).unwrap();
+            }
+        })
+        .unwrap();
+    let got = pool.run_until(fizzer.apply_stream(receiver).collect::<Vec<_>>());
+    assert_eq!(fizzer.apply(1..=16).collect::<Vec<_>>(), got);
+    assert_eq!("fizz!", got[11]);
+}
+
+#[test]
+fn test_stats_fizz_buzz() {
+    let stats = fizz_buzz::<u64>().stats(1..=100).unwrap();
+    assert_eq!(100, stats.total);
+    let counts = stats
+        .outputs
+        .iter()
+        .map(|count| (count.output.as_deref(), count.count))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (None, 53),
+            (Some("fizz"), 27),
+            (Some("buzz"), 14),
+            (Some("fizzbuzz"), 6)
+        ],
+        counts
+    );
+    assert_eq!(vec![33, 20], stats.hits);
+    assert_eq!(
+        Some(Run {
+            start: 1,
+            length: 2
+        }),
+        stats.longest_run
+    );
+}
+
+#[test]
+fn test_stats_periodic_matches_scan() {
+    use std::ops::RangeInclusive;
+
+    let periodic = fizz_buzz::<u64>()
+        .add_matcher(Matcher::divisible_by(7, "bang"))
+        .with_strategy(Strategy::FirstMatch);
+    let scanned = Fizzy::new()
+        .add_matcher(Matcher::new(|n: u64| n.is_multiple_of(3), "fizz"))
+        .add_matcher(Matcher::new(|n: u64| n.is_multiple_of(5), "buzz"))
+        .add_matcher(Matcher::new(|n: u64| n.is_multiple_of(7), "bang"))
+        .with_strategy(Strategy::FirstMatch);
+    for range in [0..=0, 1..=1, 4..=4, 0..=104, 11..=500, 209..=1_000] {
+        assert_eq!(scanned.stats(range.clone()), periodic.stats(range));
+    }
+    let plain = Fizzy::<u64>::new().add_matcher(Matcher::divisible_by(1_000, "k"));
+    let (stats, empty) = (
+        plain.stats(1..=999).unwrap(),
+        Fizzy::<u64>::new().stats(1..=999).unwrap(),
+    );
+    assert_eq!(empty.outputs, stats.outputs);
+    assert_eq!(empty.longest_run, stats.longest_run);
+    assert_eq!(
+        0,
+        fizz_buzz::<u64>()
+            .stats(RangeInclusive::new(5, 4))
+            .unwrap()
+            .total
+    );
+}
+
+#[test]
+fn test_stats_huge_range() {
+    let stats = fizz_buzz::<u64>()
+        .add_matcher(Matcher::divisible_by(4, "baz"))
+        .stats(1..=1_000_000_000_000)
+        .unwrap();
+    assert_eq!(1_000_000_000_000, stats.total);
+    assert_eq!(
+        vec![333_333_333_333, 200_000_000_000, 250_000_000_000],
+        stats.hits
+    );
+    assert_eq!(
+        Some(Run {
+            start: 1,
+            length: 2
+        }),
+        stats.longest_run
+    );
+    let stats = Fizzy::<u64>::new()
+        .add_matcher(Matcher::divisible_by(50, "x"))
+        .stats(60..=1_000_000)
+        .unwrap();
+    assert_eq!(
+        Some(Run {
+            start: 101,
+            length: 49
+        }),
+        stats.longest_run
+    );
+}
+
+#[test]
+fn test_stats_period_larger_than_a_table() {
+    let primes = [7, 11, 13, 17, 19];
+    let periodic = primes.iter().fold(Fizzy::<u64>::new(), |fizzy, &p| {
+        fizzy.add_matcher(Matcher::divisible_by(p, p.to_string()))
+    });
+    let scanned = primes.iter().fold(Fizzy::<u64>::new(), |fizzy, &p| {
+        fizzy.add_matcher(Matcher::new(
+            move |n: u64| n.is_multiple_of(p),
+            p.to_string(),
+        ))
+    });
+    // the period, 323323, is too large to compile into a table
+    assert_eq!(None, periodic.compile().period());
+    let range = 5..=700_004;
+    assert_eq!(scanned.stats(range.clone()), periodic.stats(range));
+
+    let stats = periodic.stats(1..=1_000_000_000_000).unwrap();
+    assert_eq!(1_000_000_000_000, stats.total);
+    assert_eq!(
+        primes
+            .iter()
+            .map(|&p| 1_000_000_000_000 / p)
+            .collect::<Vec<_>>(),
+        stats.hits
+    );
+    assert_eq!(
+        Some(Run {
+            start: 1,
+            length: 6
+        }),
+        stats.longest_run
+    );
+}
+
+#[test]
+fn test_stats_outside_the_element_type() {
+    let periodic = fizz_buzz::<u8>();
+    let scanned = fizz_buzz::<u8>().add_matcher(Matcher::new(|n: u8| n == 7, "bang"));
+    for fizzy in [&periodic, &scanned].iter() {
+        assert_eq!(Err(StatsError::OutOfRange(1_000)), fizzy.stats(0..=1_000));
+        assert_eq!(Err(StatsError::OutOfRange(256)), fizzy.stats(256..=300));
+        assert_eq!(255, fizzy.stats(1..=255).unwrap().total);
+    }
+    assert_eq!(
+        "1000 cannot be represented in the element type",
+        StatsError::OutOfRange(1_000).to_string()
+    );
+}
+
+fn game_rules() -> RuleSet {
+    RuleSet {
+        rules: vec![
+            WordRule {
+                rule: Rule::Divisor(3),
+                word: String::from("fizz"),
+                priority: 0,
+                suppresses: vec![String::from("bang")],
+            },
+            WordRule {
+                rule: Rule::Digit(7),
+                word: String::from("bang"),
+                priority: 0,
+                suppresses: Vec::new(),
+            },
+            WordRule {
+                rule: Rule::Range { start: -5, end: 0 },
+                word: String::from("neg"),
+                priority: 1,
+                suppresses: Vec::new(),
+            },
+            WordRule {
+                rule: Rule::Expression(String::from("n % 5 == 0 && n > 10")),
+                word: String::from("buzz"),
+                priority: 0,
+                suppresses: Vec::new(),
+            },
+        ],
+        strategy: Strategy::Suppress,
+    }
+}
+
+#[test]
+fn test_rule_set_to_fizzy() {
+    let fizzer = game_rules().to_fizzy::<i64>().unwrap();
+    let got = fizzer
+        .apply(vec![-3, 5, 7, 15, 27, 37].into_iter())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec!["negfizz", "5", "bang", "fizzbuzz", "fizz", "bang"],
+        got
+    );
+}
+
+#[test]
+fn test_rule_set_round_trip() {
+    let rules = game_rules();
+    let fizzer = rules.to_fizzy::<i64>().unwrap();
+    assert_eq!(Ok(rules), RuleSet::from_fizzy(&fizzer));
+
+    let fizzer = fizz_buzz::<u32>()
+        .add_matcher(predicates::contains_digit(3, "three").with_priority(2))
+        .add_matcher(expr::parse_rule("(n - 1) * 2 > -(n % 4) || !(n == 8) => \"x\"").unwrap());
+    let rules = RuleSet::from_fizzy(&fizzer).unwrap();
+    assert_eq!(
+        Rule::Expression(String::from("(n - 1) * 2 > -(n % 4) || !(n == 8)")),
+        rules.rules[3].rule
+    );
+    let rebuilt = rules.to_fizzy::<u32>().unwrap();
+    assert_eq!(
+        fizzer.apply(1..=40).collect::<Vec<_>>(),
+        rebuilt.apply(1..=40).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_rule_set_round_trip_of_ranges() {
+    let fizzer: Fizzy<i32> = fizzy! {
+        in(13..20) => "teen",
+        in(-3..=-1) => "neg",
+        3 => "fizz",
+    };
+    let rules = RuleSet::from_fizzy(&fizzer).unwrap();
+    assert_eq!(Rule::Range { start: 13, end: 20 }, rules.rules[0].rule);
+    assert_eq!(Rule::Range { start: -3, end: 0 }, rules.rules[1].rule);
+    let rebuilt = rules.to_fizzy::<i32>().unwrap();
+    assert_eq!(
+        fizzer.apply(-5..=25).collect::<Vec<_>>(),
+        rebuilt.apply(-5..=25).collect::<Vec<_>>()
+    );
+    // the end is excluded, as in `in(13..20)`
+    let teen = RuleSet {
+        rules: rules.rules[..1].to_vec(),
+        strategy: Strategy::default(),
+    }
+    .to_fizzy::<i32>()
+    .unwrap();
+    assert_eq!(
+        vec!["12", "teen", "teen", "20"],
+        teen.apply([12, 13, 19, 20].iter().cloned())
+            .collect::<Vec<_>>()
+    );
+
+    let unbounded = Fizzy::<i32>::new().add_matcher(predicates::in_range(5.., "big"));
+    assert_eq!(
+        Err(RuleSetError::NotRepresentable(0)),
+        RuleSet::from_fizzy(&unbounded)
+    );
+    let floats = Fizzy::<f64>::new().add_matcher(predicates::in_range(0.5..1.5, "one"));
+    assert_eq!(
+        Err(RuleSetError::NotRepresentable(0)),
+        RuleSet::from_fizzy(&floats)
+    );
+}
+
+#[test]
+fn test_rule_set_errors() {
+    let fizzer = fizz_buzz::<i32>()
+        .add_matcher(Matcher::new(|n: i32| n > 100, "big"))
+        .add_matcher(Matcher::divisible_by(2, "even").not());
+    assert_eq!(
+        Err(RuleSetError::NotRepresentable(2)),
+        RuleSet::from_fizzy(&fizzer)
+    );
+    let mut rules = game_rules();
+    rules.rules[1].rule = Rule::Digit(10);
+    assert_eq!(
+        Some(RuleSetError::NotADigit(1)),
+        rules.to_fizzy::<i32>().err()
+    );
+    rules.rules[1].rule = Rule::Divisor(0);
+    assert_eq!(
+        Some(RuleSetError::ZeroDivisor(1)),
+        rules.to_fizzy::<i32>().err()
+    );
+    rules.rules[1].rule = Rule::Divisor(300);
+    assert_eq!(
+        Some(RuleSetError::DivisorTooLarge(1)),
+        rules.to_fizzy::<u8>().err()
+    );
+    assert!(rules.to_fizzy::<u16>().is_ok());
+    rules.rules[1].rule = Rule::Expression(String::from("n +"));
+    let err = rules.to_fizzy::<i32>().err().unwrap();
+    assert!(matches!(err, RuleSetError::Expression(1, _)));
+    assert!(err.to_string().starts_with("rule 1: column 4:"));
+}
+
+#[test]
+#[cfg(feature = "serde")]
+fn test_rule_set_serde() {
+    let rules = game_rules();
+    let json = serde_json::to_string(&rules).unwrap();
+    assert_eq!(rules, serde_json::from_str::<RuleSet>(&json).unwrap());
+
+    let stored = r#"{
+        "rules": [
+            {"divisor": 3, "word": "fizz"},
+            {"divisor": 5, "word": "buzz"},
+            {"range": {"start": 1, "end": 3}, "word": "low", "priority": 1}
+        ],
+        "strategy": "first_match"
+    }"#;
+    let fizzer = serde_json::from_str::<RuleSet>(stored)
+        .unwrap()
+        .to_fizzy::<u64>()
+        .unwrap();
+    let got = fizzer.apply(1..=5).collect::<Vec<_>>();
+    assert_eq!(vec!["low", "low", "fizz", "4", "buzz"], got);
+}
@@ -1,5 +1,51 @@
-use anyhow::Error;
-use std::fs;
+use regex::{Regex, RegexBuilder};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+mod walk;
+
+/// Why a search could not be done.
+#[derive(Debug)]
+pub enum GrepError {
+    /// a file could not be read
+    Io {
+        file_name: String,
+        source: io::Error,
+    },
+    /// the pattern given with `-E` is not a valid regular expression
+    InvalidPattern(regex::Error),
+    /// a glob given with `--include`, `--exclude` or `--exclude-dir` is not valid
+    InvalidGlob(globset::Error),
+    /// a directory could not be walked with `-r`
+    Walk(ignore::Error),
+}
+
+impl fmt::Display for GrepError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GrepError::Io { file_name, source } => write!(f, "{}: {}", file_name, source),
+            GrepError::InvalidPattern(err) => write!(f, "invalid pattern: {}", err),
+            GrepError::InvalidGlob(err) => write!(f, "invalid glob: {}", err),
+            GrepError::Walk(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for GrepError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GrepError::Io { source, .. } => Some(source),
+            GrepError::InvalidPattern(err) => Some(err),
+            GrepError::InvalidGlob(err) => Some(err),
+            GrepError::Walk(err) => Some(err),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Flags {
@@ -8,21 +54,88 @@ pub struct Flags {
     case_insensitive: bool,
     invert_result: bool,
     match_entire_line: bool,
+    extended_regexp: bool,
+    recursive: bool,
+    /// with `-r`, only files whose names match one of these globs are searched
+    include: Vec<String>,
+    /// with `-r`, files whose names match one of these globs are skipped
+    exclude: Vec<String>,
+    /// with `-r`, directories whose names match one of these globs are skipped
+    exclude_dir: Vec<String>,
+    /// how many lines to print after each match
+    after_context: usize,
+    /// how many lines to print before each match
+    before_context: usize,
 }
 
+/// the flags which take a value, which can also be written right after them
+const SHORT_FLAGS_WITH_VALUE: [&str; 3] = ["-A", "-B", "-C"];
+
 impl Flags {
+    /// `--include`, `--exclude`, `--exclude-dir` and the context flags take a
+    /// value either as the next flag or after an `=`, as in `--include=*.rs`;
+    /// `-A`, `-B` and `-C` also take it right after them, as in `-A2`. A
+    /// context flag whose value is not a number is ignored, as is any flag
+    /// not listed here; a next flag which is not a number is not taken as
+    /// its value, so `-A -n` still turns on `-n`.
     pub fn new(flags: &[&str]) -> Self {
-        flags.iter().fold(Flags::default_flags(), |mut acc, flag| {
-            match *flag {
+        let mut acc = Flags::default_flags();
+        let mut flags = flags.iter().peekable();
+        while let Some(flag) = flags.next() {
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) if name.starts_with("--") => (name, Some(value)),
+                _ if flag.len() > 2
+                    && SHORT_FLAGS_WITH_VALUE
+                        .iter()
+                        .any(|short| flag.starts_with(short)) =>
+                {
+                    let (name, value) = flag.split_at(2);
+                    (name, Some(value))
+                }
+                _ => (*flag, None),
+            };
+            let mut context = || match value {
+                Some(lines) => lines.parse::<usize>().ok(),
+                None => {
+                    let lines = flags.peek().and_then(|lines| lines.parse::<usize>().ok());
+                    if lines.is_some() {
+                        flags.next();
+                    }
+                    lines
+                }
+            };
+            match name {
                 "-n" => acc.print_line_number = true,
                 "-l" => acc.print_file_name_only = true,
                 "-i" => acc.case_insensitive = true,
                 "-v" => acc.invert_result = true,
                 "-x" => acc.match_entire_line = true,
+                "-E" => acc.extended_regexp = true,
+                "-r" => acc.recursive = true,
+                "--include" | "--exclude" | "--exclude-dir" => {
+                    let globs = match name {
+                        "--include" => &mut acc.include,
+                        "--exclude" => &mut acc.exclude,
+                        _ => &mut acc.exclude_dir,
+                    };
+                    globs.extend(value.or_else(|| flags.next().copied()).map(String::from));
+                }
+                "-A" | "--after-context" => {
+                    acc.after_context = context().unwrap_or(acc.after_context)
+                }
+                "-B" | "--before-context" => {
+                    acc.before_context = context().unwrap_or(acc.before_context)
+                }
+                "-C" | "--context" => {
+                    if let Some(lines) = context() {
+                        acc.after_context = lines;
+                        acc.before_context = lines;
+                    }
+                }
                 _ => (),
             }
-            acc
-        })
+        }
+        acc
     }
     fn default_flags() -> Self {
         Flags {
@@ -31,7 +144,46 @@ impl Flags {
             case_insensitive: false,
             match_entire_line: false,
             invert_result: false,
+            extended_regexp: false,
+            recursive: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            exclude_dir: Vec::new(),
+            after_context: 0,
+            before_context: 0,
+        }
+    }
+
+    fn prints_context(&self) -> bool {
+        !self.print_file_name_only && (self.after_context > 0 || self.before_context > 0)
+    }
+}
+
+/// The pattern, prepared once for a whole search.
+enum Pattern {
+    /// searched for as it is, lowercased with `-i`
+    Literal(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn new(pattern: &str, flags: &Flags) -> Result<Self, GrepError> {
+        // a pattern without metacharacters keeps the plain string search
+        if !flags.extended_regexp || regex::escape(pattern) == pattern {
+            return Ok(Pattern::Literal(match flags.case_insensitive {
+                true => pattern.to_lowercase(),
+                false => String::from(pattern),
+            }));
         }
+        let source = match flags.match_entire_line {
+            true => format!("^(?:{})$", pattern),
+            false => String::from(pattern),
+        };
+        RegexBuilder::new(&source)
+            .case_insensitive(flags.case_insensitive)
+            .build()
+            .map(Pattern::Regex)
+            .map_err(GrepError::InvalidPattern)
     }
 }
 
@@ -43,11 +195,13 @@ fn match_text(pattern: &str, flags: &Flags, text: &str) -> bool {
     }
 }
 
-fn has_match(pattern: &str, flags: &Flags, line: &str) -> bool {
-    let matches_pattern = if flags.case_insensitive {
-        match_text(&pattern.to_lowercase(), flags, &line.to_lowercase())
-    } else {
-        match_text(pattern, flags, line)
+fn has_match(pattern: &Pattern, flags: &Flags, line: &str) -> bool {
+    let matches_pattern = match pattern {
+        Pattern::Literal(literal) if flags.case_insensitive => {
+            match_text(literal, flags, &line.to_lowercase())
+        }
+        Pattern::Literal(literal) => match_text(literal, flags, line),
+        Pattern::Regex(regex) => regex.is_match(line),
     };
 
     match flags.invert_result {
@@ -57,53 +211,164 @@ fn has_match(pattern: &str, flags: &Flags, line: &str) -> bool {
     }
 }
 
+/// the separator between the file name, line number and matching line
+const MATCH_SEPARATOR: char = ':';
+/// the separator between the file name, line number and context line
+const CONTEXT_SEPARATOR: char = '-';
+/// the line between groups of matches and context which are not adjacent
+const GROUP_SEPARATOR: &str = "--";
+
 fn format_match(
     flags: &Flags,
     include_file_name: bool,
     file_name: &str,
+    separator: char,
     (index, line): (usize, &str),
 ) -> String {
     let formatted_line = match flags.print_line_number {
-        true => format!("{}:{}", index + 1, line),
+        true => format!("{}{}{}", index + 1, separator, line),
         false => String::from(line),
     };
     match include_file_name {
-        true => format!("{}:{}", file_name, formatted_line),
+        true => format!("{}{}{}", file_name, separator, formatted_line),
         false => formatted_line,
     }
 }
 
-fn match_file_content(
-    pattern: &str,
+/// the longest line which is searched; a longer one fails the search of its
+/// file instead of being buffered without bound
+const MAX_LINE_LENGTH: usize = 16 << 20;
+
+/// The lines of a reader, read chunk by chunk into a reused buffer, so that
+/// only the current line, of at most `MAX_LINE_LENGTH` bytes, is held in
+/// memory. Bytes which are not valid UTF-8 are replaced with U+FFFD.
+struct Lines<R> {
+    reader: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: BufRead> Lines<R> {
+    fn new(reader: R) -> Self {
+        Lines {
+            reader,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// the next line without its `\n`, or None at the end of the input
+    fn next_line(&mut self) -> io::Result<Option<Cow<'_, str>>> {
+        self.buffer.clear();
+        loop {
+            let chunk = match self.reader.fill_buf() {
+                Ok(chunk) => chunk,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if chunk.is_empty() {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                break;
+            }
+            let end = chunk.iter().position(|&byte| byte == b'\n');
+            let line = &chunk[..end.unwrap_or(chunk.len())];
+            if self.buffer.len() + line.len() > MAX_LINE_LENGTH {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line longer than {} bytes", MAX_LINE_LENGTH),
+                ));
+            }
+            self.buffer.extend_from_slice(line);
+            let used = line.len() + usize::from(end.is_some());
+            self.reader.consume(used);
+            if end.is_some() {
+                break;
+            }
+        }
+        Ok(Some(String::from_utf8_lossy(&self.buffer)))
+    }
+}
+
+fn match_file_content<R: BufRead>(
+    pattern: &Pattern,
     flags: &Flags,
     include_file_name: bool,
     file_name: &str,
-    content: &str,
-) -> Vec<String> {
-    let mut matches = content
-        .split("\n")
-        .enumerate()
-        .filter(|(_, line)| has_match(pattern, flags, line));
-
-    match flags.print_file_name_only {
-        true => matches
-            .next()
-            .map_or(vec![], |_| vec![String::from(file_name)]),
-        false => matches
-            .map(|pair| format_match(flags, include_file_name, file_name, pair))
-            .filter(|result| !result.is_empty())
-            .collect::<Vec<_>>(),
+    reader: R,
+) -> io::Result<Vec<String>> {
+    let mut lines = Lines::new(reader);
+    let mut matches = Vec::new();
+    let format = |separator, pair: (usize, &str)| {
+        format_match(flags, include_file_name, file_name, separator, pair)
+    };
+
+    // the unprinted lines just before the current one, up to `-B` of them
+    let mut before: VecDeque<(usize, String)> = VecDeque::new();
+    // how many more lines to print after the last match
+    let mut after = 0;
+    let mut last_printed: Option<usize> = None;
+
+    let mut index = 0;
+    while let Some(line) = lines.next_line()? {
+        if has_match(pattern, flags, &line) {
+            // the rest of the file need not be read to name it
+            if flags.print_file_name_only {
+                return Ok(vec![String::from(file_name)]);
+            }
+            let first = before.front().map_or(index, |(first, _)| *first);
+            if flags.prints_context() && last_printed.is_some_and(|last| first > last + 1) {
+                matches.push(String::from(GROUP_SEPARATOR));
+            }
+            for (index, line) in before.drain(..) {
+                matches.push(format(CONTEXT_SEPARATOR, (index, &line)));
+            }
+            let result = format(MATCH_SEPARATOR, (index, &line));
+            if !result.is_empty() {
+                matches.push(result);
+            }
+            last_printed = Some(index);
+            after = flags.after_context;
+        } else if after > 0 {
+            matches.push(format(CONTEXT_SEPARATOR, (index, &line)));
+            last_printed = Some(index);
+            after -= 1;
+        } else if flags.before_context > 0 {
+            if before.len() == flags.before_context {
+                before.pop_front();
+            }
+            before.push_back((index, line.into_owned()));
+        }
+        index += 1;
     }
+    Ok(matches)
 }
 
-pub fn grep(pattern: &str, flags: &Flags, files: &[&str]) -> Result<Vec<String>, Error> {
+pub fn grep(pattern: &str, flags: &Flags, files: &[&str]) -> Result<Vec<String>, GrepError> {
+    let pattern = Pattern::new(pattern, flags)?;
     let mut matching_lines: Vec<String> = Vec::new();
-    let include_file_name = files.len() > 1;
+    // like GNU grep, name the file as soon as a directory is searched
+    let include_file_name =
+        files.len() > 1 || flags.recursive && files.iter().any(|file| Path::new(file).is_dir());
+    let files = match flags.recursive {
+        true => walk::files_to_search(files, flags)?,
+        false => files.iter().map(|file| String::from(*file)).collect(),
+    };
 
     for file_name in files.iter() {
-        let content = fs::read_to_string(file_name)?;
-        let matches = match_file_content(pattern, &flags, include_file_name, file_name, &content);
+        let matches = File::open(file_name)
+            .map(BufReader::new)
+            .and_then(|reader| {
+                match_file_content(&pattern, flags, include_file_name, file_name, reader)
+            })
+            .map_err(|source| GrepError::Io {
+                file_name: file_name.clone(),
+                source,
+            })?;
 
+        // groups in different files are never adjacent
+        if flags.prints_context() && !matching_lines.is_empty() && !matches.is_empty() {
+            matching_lines.push(String::from(GROUP_SEPARATOR));
+        }
         matching_lines.extend(matches);
     }
     Ok(matching_lines)
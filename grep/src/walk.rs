@@ -0,0 +1,67 @@
+//! Expanding the paths given to `grep -r` into the files to search.
+//!
+//! Directories are walked like ripgrep does: files listed in `.gitignore`
+//! and `.ignore` files are skipped, as are hidden files and directories.
+//! Paths given explicitly are always searched. A subdirectory which cannot
+//! be read is skipped, like GNU grep does, while a path given explicitly
+//! which cannot be read fails the search.
+
+use crate::{Flags, GrepError};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+use std::path::Path;
+
+fn glob_set(globs: &[String]) -> Result<GlobSet, GrepError> {
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(Glob::new(glob).map_err(GrepError::InvalidGlob)?);
+    }
+    builder.build().map_err(GrepError::InvalidGlob)
+}
+
+/// whether the last component of `path` matches one of `globs`
+fn name_matches(globs: &GlobSet, path: &Path) -> bool {
+    path.file_name().is_some_and(|name| globs.is_match(name))
+}
+
+/// the files under `paths`, each directory in order of file name, which the
+/// ignore files, `--include`, `--exclude` and `--exclude-dir` let through
+pub(crate) fn files_to_search(paths: &[&str], flags: &Flags) -> Result<Vec<String>, GrepError> {
+    let include = glob_set(&flags.include)?;
+    let exclude = glob_set(&flags.exclude)?;
+    let exclude_dir = glob_set(&flags.exclude_dir)?;
+    let mut files = Vec::new();
+
+    for path in paths {
+        let walker = WalkBuilder::new(path)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b))
+            .filter_entry({
+                let exclude_dir = exclude_dir.clone();
+                move |entry| {
+                    let is_dir = entry.file_type().is_some_and(|kind| kind.is_dir());
+                    entry.depth() == 0 || !is_dir || !name_matches(&exclude_dir, entry.path())
+                }
+            })
+            .build();
+
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) if err.depth().is_some_and(|depth| depth > 0) => continue,
+                Err(err) => return Err(GrepError::Walk(err)),
+            };
+            if entry.file_type().is_some_and(|kind| kind.is_dir()) {
+                continue;
+            }
+            if entry.depth() > 0
+                && (!include.is_empty() && !name_matches(&include, entry.path())
+                    || name_matches(&exclude, entry.path()))
+            {
+                continue;
+            }
+            files.push(entry.path().display().to_string());
+        }
+    }
+    Ok(files)
+}
@@ -1,6 +1,7 @@
-use grep::{grep, Flags};
+use grep::{grep, Flags, GrepError};
 
 use std::fs;
+use std::path::Path;
 
 static ILIAD_CONTENT: &'static str = "Achilles sing, O Goddess! Peleus' son;
 His wrath pernicious, who ten thousand woes
@@ -539,3 +540,615 @@ set_up_test_case!(
         ]
     )
 );
+
+// Test regular expressions
+
+#[test]
+fn test_invalid_regex_returns_error() {
+    let flags = Flags::new(&["-E"]);
+
+    let files = vec!["test_invalid_regex_returns_error_iliad.txt"];
+
+    let test_fixture = Fixture::new(&files);
+
+    test_fixture.set_up();
+
+    let error = grep("Achill(es", &flags, &files).unwrap_err();
+    assert!(matches!(error, GrepError::InvalidPattern(_)));
+    assert!(error.to_string().starts_with("invalid pattern:"));
+}
+
+#[test]
+fn test_nonexistent_file_returns_io_error() {
+    let flags = Flags::new(&[]);
+
+    let files = vec!["test_nonexistent_file_returns_io_error_iliad.txt"];
+
+    match grep("Agamemnon", &flags, &files) {
+        Err(GrepError::Io { file_name, .. }) => assert_eq!(files[0], file_name),
+        other => panic!("expected an io error, got {:?}", other),
+    }
+}
+
+set_up_test_case!(
+    #[test]
+    test_regex_without_extended_flag_is_literal(
+        pattern = "Ach.*s",
+        flags = [],
+        files = ["iliad.txt"],
+        expected = []
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_regex_one_file(
+        pattern = "^(Of|To) [a-z]+",
+        flags = ["-E"],
+        files = ["iliad.txt"],
+        expected = ["To dogs and to all ravening fowls a prey,"]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_regex_with_case_insensitive_and_line_number_flags(
+        pattern = "achill[a-z]+ (sing|from)",
+        flags = ["-E", "-i", "-n"],
+        files = ["iliad.txt"],
+        expected = [
+            "1:Achilles sing, O Goddess! Peleus' son;",
+            "8:The noble Chief Achilles from the son"
+        ]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_regex_with_match_entire_line_flag(
+        pattern = "I .* me\\.|If .*",
+        flags = ["-E", "-x"],
+        files = ["midsummer_night.txt"],
+        expected = [
+            "I do entreat your grace to pardon me.",
+            "If I refuse to wed Demetrius."
+        ]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_regex_with_inverted_flag_multiple_files(
+        pattern = "[,;.]$",
+        flags = ["-E", "-v"],
+        files = ["iliad.txt", "paradise_lost.txt"],
+        prefix_expected = [
+            "iliad.txt:His wrath pernicious, who ten thousand woes",
+            "iliad.txt:Caused to Achaia's host, sent many a soul",
+            "iliad.txt:And Heroes gave (so stood the will of Jove)",
+            "iliad.txt:When fierce dispute had separated once",
+            "iliad.txt:The noble Chief Achilles from the son",
+            "paradise_lost.txt:Of Mans First Disobedience, and the Fruit",
+            "paradise_lost.txt:Of that Forbidden Tree, whose mortal tast",
+            "paradise_lost.txt:With loss of Eden, till one greater Man",
+            "paradise_lost.txt:Sing Heav'nly Muse, that on the secret top",
+            "paradise_lost.txt:Of Oreb, or of Sinai, didst inspire",
+            "paradise_lost.txt:That Shepherd, who first taught the chosen Seed"
+        ]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_regex_unicode_case_insensitive(
+        pattern = "^красный|\\bневе\\.$",
+        flags = ["-E", "-i"],
+        files = ["in_the_white_night.txt"],
+        expected = ["Отражается в Неве.", "Красный месяц, тихий шум?.."]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_extended_flag_with_literal_pattern(
+        pattern = "the son",
+        flags = ["-E", "-i"],
+        files = ["iliad.txt"],
+        expected = ["The noble Chief Achilles from the son"]
+    )
+);
+
+// Test searching directories recursively
+
+/// A directory tree named after the test, removed again when dropped. The
+/// files end in `.md`, since the repository ignores `*.txt`.
+struct DirectoryFixture {
+    root: &'static str,
+}
+
+impl DirectoryFixture {
+    fn new(root: &'static str, files: &[(&str, &str)]) -> Self {
+        let fixture = DirectoryFixture { root };
+        for (path, content) in files {
+            let path = Path::new(root).join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content)
+                .unwrap_or_else(|_| panic!("Error setting up file '{}'", path.display()));
+        }
+        fixture
+    }
+}
+
+impl Drop for DirectoryFixture {
+    fn drop(&mut self) {
+        fs::remove_dir_all(self.root)
+            .unwrap_or_else(|_| panic!("Could not delete directory '{}'", self.root));
+    }
+}
+
+#[test]
+fn test_recursive_search_in_directory() {
+    let root = "test_recursive_search_in_directory";
+    let _fixture = DirectoryFixture::new(
+        root,
+        &[
+            ("poems/iliad.md", ILIAD_CONTENT),
+            ("paradise_lost.md", PARADISE_LOST_CONTENT),
+        ],
+    );
+
+    let flags = Flags::new(&["-r", "-n", "-E"]);
+
+    assert_eq!(
+        grep("^Of (Atreus|Oreb)", &flags, &[root]).unwrap(),
+        [
+            "test_recursive_search_in_directory/paradise_lost.md:7:Of Oreb, or of Sinai, didst inspire",
+            "test_recursive_search_in_directory/poems/iliad.md:9:Of Atreus, Agamemnon, King of men."
+        ]
+    );
+}
+
+#[test]
+fn test_recursive_search_honours_ignore_files() {
+    let root = "test_recursive_search_honours_ignore_files";
+    let _fixture = DirectoryFixture::new(
+        root,
+        &[
+            (".gitignore", "drafts/\n"),
+            (".ignore", "*_copy.md\n"),
+            ("drafts/iliad.md", ILIAD_CONTENT),
+            ("iliad_copy.md", ILIAD_CONTENT),
+            ("iliad.md", ILIAD_CONTENT),
+        ],
+    );
+
+    let flags = Flags::new(&["-r", "-l"]);
+
+    assert_eq!(
+        grep("Agamemnon", &flags, &[root]).unwrap(),
+        ["test_recursive_search_honours_ignore_files/iliad.md"]
+    );
+}
+
+#[test]
+fn test_recursive_search_skips_hidden_files() {
+    let root = "test_recursive_search_skips_hidden_files";
+    let _fixture = DirectoryFixture::new(
+        root,
+        &[
+            (".hidden/iliad.md", ILIAD_CONTENT),
+            (".iliad.md", ILIAD_CONTENT),
+            ("iliad.md", ILIAD_CONTENT),
+        ],
+    );
+
+    let flags = Flags::new(&["-r", "-l"]);
+
+    assert_eq!(
+        grep("Agamemnon", &flags, &[root]).unwrap(),
+        ["test_recursive_search_skips_hidden_files/iliad.md"]
+    );
+}
+
+#[test]
+fn test_recursive_search_with_include_glob() {
+    let root = "test_recursive_search_with_include_glob";
+    let _fixture = DirectoryFixture::new(
+        root,
+        &[
+            ("iliad.md", ILIAD_CONTENT),
+            ("iliad.rs", ILIAD_CONTENT),
+            ("src/iliad.rs", ILIAD_CONTENT),
+        ],
+    );
+
+    for flags in [["-r", "--include", "*.rs"], ["-r", "--include=*.rs", "-l"]].iter() {
+        assert_eq!(
+            grep("Agamemnon", &Flags::new(flags), &[root]).unwrap(),
+            [
+                "test_recursive_search_with_include_glob/iliad.rs",
+                "test_recursive_search_with_include_glob/src/iliad.rs"
+            ]
+            .iter()
+            .map(|file| match flags.contains(&"-l") {
+                true => String::from(*file),
+                false => format!("{}:Of Atreus, Agamemnon, King of men.", file),
+            })
+            .collect::<Vec<_>>()
+        );
+    }
+}
+
+#[test]
+fn test_recursive_search_with_exclude_dir_glob() {
+    let root = "test_recursive_search_with_exclude_dir_glob";
+    let _fixture = DirectoryFixture::new(
+        root,
+        &[
+            ("src/iliad.md", ILIAD_CONTENT),
+            ("target/debug/iliad.md", ILIAD_CONTENT),
+            ("targets/iliad.md", ILIAD_CONTENT),
+        ],
+    );
+
+    let flags = Flags::new(&["-r", "-l", "--exclude-dir", "target"]);
+
+    assert_eq!(
+        grep("Agamemnon", &flags, &[root]).unwrap(),
+        [
+            "test_recursive_search_with_exclude_dir_glob/src/iliad.md",
+            "test_recursive_search_with_exclude_dir_glob/targets/iliad.md"
+        ]
+    );
+}
+
+#[test]
+fn test_recursive_search_with_exclude_glob() {
+    let root = "test_recursive_search_with_exclude_glob";
+    let _fixture = DirectoryFixture::new(
+        root,
+        &[
+            ("iliad.log", ILIAD_CONTENT),
+            ("iliad.md", ILIAD_CONTENT),
+            ("iliad.rs", ILIAD_CONTENT),
+            ("src/iliad.log", ILIAD_CONTENT),
+        ],
+    );
+
+    for flags in [
+        ["-r", "-l", "--exclude", "*.log"],
+        ["-r", "-l", "--exclude=*.log", "--include=*.*"],
+    ]
+    .iter()
+    {
+        assert_eq!(
+            grep("Agamemnon", &Flags::new(flags), &[root]).unwrap(),
+            [
+                "test_recursive_search_with_exclude_glob/iliad.md",
+                "test_recursive_search_with_exclude_glob/iliad.rs"
+            ]
+        );
+    }
+
+    let flags = Flags::new(&["-r", "-l", "--include", "*.md", "--exclude", "*.rs"]);
+
+    assert_eq!(
+        grep("Agamemnon", &flags, &[root]).unwrap(),
+        ["test_recursive_search_with_exclude_glob/iliad.md"]
+    );
+}
+
+#[test]
+fn test_recursive_search_of_one_file_omits_file_name() {
+    let root = "test_recursive_search_of_one_file_omits_file_name";
+    let _fixture = DirectoryFixture::new(root, &[("iliad.md", ILIAD_CONTENT)]);
+
+    let flags = Flags::new(&["-r"]);
+
+    assert_eq!(
+        grep(
+            "Agamemnon",
+            &flags,
+            &["test_recursive_search_of_one_file_omits_file_name/iliad.md"]
+        )
+        .unwrap(),
+        ["Of Atreus, Agamemnon, King of men."]
+    );
+}
+
+#[test]
+fn test_directory_without_recursive_flag_returns_io_error() {
+    let root = "test_directory_without_recursive_flag_returns_io_error";
+    let _fixture = DirectoryFixture::new(root, &[("iliad.md", ILIAD_CONTENT)]);
+
+    let flags = Flags::new(&[]);
+
+    assert!(matches!(
+        grep("Agamemnon", &flags, &[root]),
+        Err(GrepError::Io { .. })
+    ));
+}
+
+#[test]
+fn test_invalid_glob_returns_error() {
+    let root = "test_invalid_glob_returns_error";
+    let _fixture = DirectoryFixture::new(root, &[("iliad.md", ILIAD_CONTENT)]);
+
+    let flags = Flags::new(&["-r", "--include", "[iliad"]);
+
+    assert!(matches!(
+        grep("Agamemnon", &flags, &[root]),
+        Err(GrepError::InvalidGlob(_))
+    ));
+}
+
+// Test reading files line by line
+
+/// A file with raw bytes, removed again when dropped.
+struct BytesFixture {
+    file_name: &'static str,
+}
+
+impl BytesFixture {
+    fn new(file_name: &'static str, content: &[u8]) -> Self {
+        fs::write(file_name, content)
+            .unwrap_or_else(|_| panic!("Error setting up file '{}'", file_name));
+        BytesFixture { file_name }
+    }
+}
+
+impl Drop for BytesFixture {
+    fn drop(&mut self) {
+        fs::remove_file(self.file_name)
+            .unwrap_or_else(|_| panic!("Could not delete file '{}'", self.file_name));
+    }
+}
+
+#[test]
+fn test_non_utf8_content_is_searched_lossily() {
+    let file_name = "test_non_utf8_content_is_searched_lossily_latin1.txt";
+    let _fixture = BytesFixture::new(file_name, b"caf\xe9 au lait\nth\xe9 noir\ncaf\xe9 noir\n");
+
+    let flags = Flags::new(&["-n"]);
+
+    assert_eq!(
+        grep("noir", &flags, &[file_name]).unwrap(),
+        ["2:th\u{FFFD} noir", "3:caf\u{FFFD} noir"]
+    );
+}
+
+#[test]
+fn test_non_utf8_content_with_regex() {
+    let file_name = "test_non_utf8_content_with_regex_binary.txt";
+    let _fixture = BytesFixture::new(
+        file_name,
+        b"\x00\xff\xfe header\nkey=value\n\x80\x81 key=other",
+    );
+
+    let flags = Flags::new(&["-E", "-x"]);
+
+    assert_eq!(
+        grep("[^=]*key=[a-z]+", &flags, &[file_name]).unwrap(),
+        ["key=value", "\u{FFFD}\u{FFFD} key=other"]
+    );
+}
+
+#[test]
+fn test_many_lines_are_numbered_in_order() {
+    let file_name = "test_many_lines_are_numbered_in_order_log.txt";
+    let content = (1..=100_000)
+        .map(|n| format!("line {}\n", n))
+        .collect::<String>();
+    let _fixture = BytesFixture::new(file_name, content.as_bytes());
+
+    let flags = Flags::new(&["-n", "-x"]);
+
+    assert_eq!(
+        grep("line 99999", &flags, &[file_name]).unwrap(),
+        ["99999:line 99999"]
+    );
+}
+
+#[test]
+fn test_overlong_line_returns_io_error() {
+    let file_name = "test_overlong_line_returns_io_error.txt";
+    let mut content = b"Achilles\n".to_vec();
+    content.resize(content.len() + (16 << 20) + 1, b'a');
+    let _fixture = BytesFixture::new(file_name, &content);
+
+    let flags = Flags::new(&[]);
+    let error = grep("Achilles", &flags, &[file_name]).unwrap_err();
+
+    assert!(matches!(error, GrepError::Io { .. }));
+    assert_eq!(
+        error.to_string(),
+        "test_overlong_line_returns_io_error.txt: line longer than 16777216 bytes"
+    );
+}
+
+#[test]
+fn test_trailing_newline_is_not_an_empty_line() {
+    let file_name = "test_trailing_newline_is_not_an_empty_line_lines.txt";
+    let _fixture = BytesFixture::new(file_name, b"first\n\nthird\n");
+
+    let flags = Flags::new(&["-n", "-x"]);
+
+    assert_eq!(grep("", &flags, &[file_name]).unwrap(), ["2:"]);
+}
+
+// Test printing context lines
+
+set_up_test_case!(
+    #[test]
+    test_after_context_separates_groups(
+        pattern = "Achilles",
+        flags = ["-n", "-A", "1"],
+        files = ["iliad.txt"],
+        expected = [
+            "1:Achilles sing, O Goddess! Peleus' son;",
+            "2-His wrath pernicious, who ten thousand woes",
+            "--",
+            "8:The noble Chief Achilles from the son",
+            "9-Of Atreus, Agamemnon, King of men."
+        ]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_before_context_without_line_numbers(
+        pattern = "Jove",
+        flags = ["-B2"],
+        files = ["iliad.txt"],
+        expected = [
+            "Caused to Achaia's host, sent many a soul",
+            "Illustrious into Ades premature,",
+            "And Heroes gave (so stood the will of Jove)"
+        ]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_context_around_consecutive_matches(
+        pattern = "Ades|Jove",
+        flags = ["-E", "-n", "-C", "1"],
+        files = ["iliad.txt"],
+        expected = [
+            "3-Caused to Achaia's host, sent many a soul",
+            "4:Illustrious into Ades premature,",
+            "5:And Heroes gave (so stood the will of Jove)",
+            "6-To dogs and to all ravening fowls a prey,"
+        ]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_adjacent_context_windows_are_merged(
+        pattern = "Caused|prey",
+        flags = ["-E", "-n", "--context=1"],
+        files = ["iliad.txt"],
+        expected = [
+            "2-His wrath pernicious, who ten thousand woes",
+            "3:Caused to Achaia's host, sent many a soul",
+            "4-Illustrious into Ades premature,",
+            "5-And Heroes gave (so stood the will of Jove)",
+            "6:To dogs and to all ravening fowls a prey,",
+            "7-When fierce dispute had separated once"
+        ]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_context_does_not_repeat_printed_lines(
+        pattern = "sing|wrath|Illustrious",
+        flags = ["-E", "-n", "-B", "2", "--after-context", "1"],
+        files = ["iliad.txt"],
+        expected = [
+            "1:Achilles sing, O Goddess! Peleus' son;",
+            "2:His wrath pernicious, who ten thousand woes",
+            "3-Caused to Achaia's host, sent many a soul",
+            "4:Illustrious into Ades premature,",
+            "5-And Heroes gave (so stood the will of Jove)"
+        ]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_context_with_inverted_flag(
+        pattern = "[a-z]",
+        flags = ["-E", "-n", "-v", "-i", "-A", "1"],
+        files = ["iliad.txt"],
+        expected = []
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_before_context_with_inverted_flag(
+        pattern = "Achilles|to",
+        flags = ["-E", "-n", "-v", "-B", "1"],
+        files = ["iliad.txt"],
+        expected = [
+            "1-Achilles sing, O Goddess! Peleus' son;",
+            "2:His wrath pernicious, who ten thousand woes",
+            "--",
+            "6-To dogs and to all ravening fowls a prey,",
+            "7:When fierce dispute had separated once",
+            "8-The noble Chief Achilles from the son",
+            "9:Of Atreus, Agamemnon, King of men."
+        ]
+    )
+);
+
+#[test]
+fn test_context_with_multiple_files() {
+    process_grep_case(
+        "Agamemnon|Forbidden",
+        &["-E", "-n", "-C", "1"],
+        &[
+            "test_context_with_multiple_files_iliad.txt",
+            "test_context_with_multiple_files_paradise_lost.txt",
+        ],
+        &[
+            "test_context_with_multiple_files_iliad.txt-8-The noble Chief Achilles from the son",
+            "test_context_with_multiple_files_iliad.txt:9:Of Atreus, Agamemnon, King of men.",
+            "--",
+            "test_context_with_multiple_files_paradise_lost.txt-1-Of Mans First Disobedience, and the Fruit",
+            "test_context_with_multiple_files_paradise_lost.txt:2:Of that Forbidden Tree, whose mortal tast",
+            "test_context_with_multiple_files_paradise_lost.txt-3-Brought Death into the World, and all our woe,",
+        ],
+    );
+}
+
+set_up_test_case!(
+    #[test]
+    test_context_with_multiple_files_without_line_numbers(
+        pattern = "Oreb",
+        flags = ["-A", "1"],
+        files = ["iliad.txt", "paradise_lost.txt"],
+        prefix_expected = [
+            "paradise_lost.txt:Of Oreb, or of Sinai, didst inspire",
+            "paradise_lost.txt-That Shepherd, who first taught the chosen Seed"
+        ]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_context_flag_does_not_take_a_flag_as_its_value(
+        pattern = "Jove",
+        flags = ["-A", "-n"],
+        files = ["iliad.txt"],
+        expected = ["5:And Heroes gave (so stood the will of Jove)"]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_huge_before_context_is_not_allocated_up_front(
+        pattern = "Jove",
+        flags = ["-B", "100000000000000"],
+        files = ["iliad.txt"],
+        expected = [
+            "Achilles sing, O Goddess! Peleus' son;",
+            "His wrath pernicious, who ten thousand woes",
+            "Caused to Achaia's host, sent many a soul",
+            "Illustrious into Ades premature,",
+            "And Heroes gave (so stood the will of Jove)"
+        ]
+    )
+);
+
+set_up_test_case!(
+    #[test]
+    test_context_is_ignored_when_printing_file_names(
+        pattern = "Agamemnon",
+        flags = ["-l", "-C", "2"],
+        files = ["iliad.txt", "paradise_lost.txt"],
+        prefix_expected = ["iliad.txt"]
+    )
+);